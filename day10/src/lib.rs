@@ -1,8 +1,11 @@
 use std::collections::{HashMap, HashSet};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 
-pub fn solve_one(input: &str) -> Result<String> {
+/// height of the end of every trail
+const PEAK: u32 = 9;
+
+pub fn parse_input(input: &str) -> Result<Vec<Vec<u32>>> {
     let map = input
         .lines()
         .map(|line| {
@@ -11,6 +14,26 @@ pub fn solve_one(input: &str) -> Result<String> {
                 .collect::<Result<Vec<u32>>>()
         })
         .collect::<Result<Vec<Vec<u32>>>>()?;
+    if map.is_empty() || map.iter().any(|l| l.len() != map[0].len()) {
+        bail!("map is not a non-empty rectangle");
+    }
+    Ok(map)
+}
+
+fn neighbours(map: &[Vec<u32>], (i, j): (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+    let (max_i, max_j) = (map.len() - 1, map[0].len() - 1);
+    [
+        i.checked_sub(1).map(|i| (i, j)),
+        i.checked_add(1).filter(|&i| i <= max_i).map(|i| (i, j)),
+        j.checked_sub(1).map(|j| (i, j)),
+        j.checked_add(1).filter(|&j| j <= max_j).map(|j| (i, j)),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+pub fn solve_one(input: &str) -> Result<String> {
+    let map = parse_input(input)?;
     let (max_i, max_j) = (map.len() - 1, map[0].len() - 1);
     Ok((0..=max_i)
         .flat_map(|i| (0..=max_j).map(move |j| (i, j)))
@@ -20,19 +43,11 @@ pub fn solve_one(input: &str) -> Result<String> {
             if map[i][j] == step {
                 steps.insert((i, j));
             }
-            while step < 9 && !steps.is_empty() {
+            while step < PEAK && !steps.is_empty() {
                 let mut next = HashSet::new();
                 step += 1;
-                steps.iter().for_each(|&(i, j)| {
-                    vec![
-                        i.checked_sub(1).map(|i| (i, j)),
-                        i.checked_add(1).filter(|&i| i <= max_i).map(|i| (i, j)),
-                        j.checked_sub(1).map(|j| (i, j)),
-                        j.checked_add(1).filter(|&j| j <= max_j).map(|j| (i, j)),
-                    ]
-                    .into_iter()
-                    .flatten()
-                    .for_each(|(i, j): (usize, usize)| {
+                steps.iter().for_each(|&pos| {
+                    neighbours(&map, pos).for_each(|(i, j)| {
                         if map[i][j] == step {
                             next.insert((i, j));
                         }
@@ -47,14 +62,7 @@ pub fn solve_one(input: &str) -> Result<String> {
 }
 
 pub fn solve_two(input: &str) -> Result<String> {
-    let map = input
-        .lines()
-        .map(|line| {
-            line.chars()
-                .map(|c| c.to_digit(10).ok_or_else(|| anyhow!("not a digit")))
-                .collect::<Result<Vec<u32>>>()
-        })
-        .collect::<Result<Vec<Vec<u32>>>>()?;
+    let map = parse_input(input)?;
     let (max_i, max_j) = (map.len() - 1, map[0].len() - 1);
     Ok((0..=max_i)
         .flat_map(|i| (0..=max_j).map(move |j| (i, j)))
@@ -64,19 +72,11 @@ pub fn solve_two(input: &str) -> Result<String> {
             if map[i][j] == step {
                 steps.insert((i, j), 1);
             }
-            while step < 9 && !steps.is_empty() {
+            while step < PEAK && !steps.is_empty() {
                 let mut next = HashMap::new();
                 step += 1;
-                steps.iter().for_each(|(&(i, j), &count)| {
-                    vec![
-                        i.checked_sub(1).map(|i| (i, j)),
-                        i.checked_add(1).filter(|&i| i <= max_i).map(|i| (i, j)),
-                        j.checked_sub(1).map(|j| (i, j)),
-                        j.checked_add(1).filter(|&j| j <= max_j).map(|j| (i, j)),
-                    ]
-                    .into_iter()
-                    .flatten()
-                    .for_each(|(i, j): (usize, usize)| {
+                steps.iter().for_each(|(&pos, &count)| {
+                    neighbours(&map, pos).for_each(|(i, j)| {
                         if map[i][j] == step {
                             next.entry((i, j))
                                 .and_modify(|c| *c += count)
@@ -91,3 +91,92 @@ pub fn solve_two(input: &str) -> Result<String> {
         .sum::<usize>()
         .to_string())
 }
+
+/// every distinct hiking trail from the trailhead, each as the positions from height 0 to 9
+pub fn trails(map: &[Vec<u32>], trailhead: (usize, usize)) -> Result<Vec<Vec<(usize, usize)>>> {
+    let (i, j) = trailhead;
+    match map.get(i).and_then(|l| l.get(j)) {
+        Some(0) => {}
+        Some(h) => bail!("({i}, {j}) has height {h}, not a trailhead"),
+        None => bail!("({i}, {j}) is off the map"),
+    }
+    let mut trails = vec![];
+    let mut stack = vec![vec![trailhead]];
+    while let Some(trail) = stack.pop() {
+        let (i, j) = trail[trail.len() - 1];
+        if map[i][j] == PEAK {
+            trails.push(trail);
+            continue;
+        }
+        for (ni, nj) in neighbours(map, (i, j)) {
+            if map[ni][nj] == map[i][j] + 1 {
+                let mut next = trail.clone();
+                next.push((ni, nj));
+                stack.push(next);
+            }
+        }
+    }
+    trails.reverse();
+    Ok(trails)
+}
+
+/// number of hiking trails passing through each cell, the trails from below times the trails
+/// to a peak, so a trailhead's count is its rating
+pub fn trail_counts(map: &[Vec<u32>]) -> Vec<Vec<u64>> {
+    let cells = |h: u32| {
+        (0..map.len())
+            .flat_map(|i| (0..map[0].len()).map(move |j| (i, j)))
+            .filter(move |(i, j)| map[*i][*j] == h)
+    };
+    let mut from_trailhead = vec![vec![0u64; map[0].len()]; map.len()];
+    let mut to_peak = from_trailhead.clone();
+    for (i, j) in cells(0) {
+        from_trailhead[i][j] = 1;
+    }
+    for (i, j) in cells(PEAK) {
+        to_peak[i][j] = 1;
+    }
+    for h in 1..=PEAK {
+        for (i, j) in cells(h) {
+            from_trailhead[i][j] = neighbours(map, (i, j))
+                .filter(|(ni, nj)| map[*ni][*nj] == h - 1)
+                .map(|(ni, nj)| from_trailhead[ni][nj])
+                .sum();
+        }
+    }
+    for h in (0..PEAK).rev() {
+        for (i, j) in cells(h) {
+            to_peak[i][j] = neighbours(map, (i, j))
+                .filter(|(ni, nj)| map[*ni][*nj] == h + 1)
+                .map(|(ni, nj)| to_peak[ni][nj])
+                .sum();
+        }
+    }
+    from_trailhead
+        .iter()
+        .zip(&to_peak)
+        .map(|(from, to)| from.iter().zip(to).map(|(f, t)| f * t).collect())
+        .collect()
+}
+
+/// trail counts as right aligned columns, `.` where no trail passes
+pub fn render_heatmap(counts: &[Vec<u64>]) -> String {
+    let width = counts
+        .iter()
+        .flatten()
+        .max()
+        .map_or(1, |max| max.to_string().len());
+    counts
+        .iter()
+        .map(|l| {
+            l.iter()
+                .map(|c| match c {
+                    0 => format!("{:>width$}", '.'),
+                    c => format!("{c:>width$}"),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -1,6 +1,43 @@
+use anyhow::{anyhow, Result};
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day10, part1)]
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// list every hiking trail from the trailhead at row I, column J instead of solving
+    #[arg(long, value_name = "I,J", value_parser = parse_pos)]
+    trails: Option<(usize, usize)>,
+    /// print the number of trails through each cell instead of solving
+    #[arg(long)]
+    heatmap: bool,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        let map = day10::parse_input(input)?;
+        if let Some(trailhead) = self.trails {
+            let trails = day10::trails(&map, trailhead)?;
+            for trail in &trails {
+                let steps = trail
+                    .iter()
+                    .map(|(i, j)| format!("({i},{j})"))
+                    .collect::<Vec<_>>();
+                println!("{}", steps.join(" "));
+            }
+            println!("{} trails", trails.len());
+        }
+        if self.heatmap {
+            println!("{}", day10::render_heatmap(&day10::trail_counts(&map)));
+        }
+        Ok(self.trails.is_some() || self.heatmap)
+    }
+}
+
+fn parse_pos(s: &str) -> Result<(usize, usize)> {
+    let (i, j) = s.split_once(',').ok_or_else(|| anyhow!("expected I,J"))?;
+    Ok((i.trim().parse()?, j.trim().parse()?))
+}
+
+#[aoc(day10, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day10::solve_one(input)
 }
@@ -1,21 +1,32 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use num_bigint::BigUint;
 
-pub fn solve_one(input: &str) -> Result<String> {
-    let mut stones = input
+fn parse_input(input: &str) -> Result<Vec<u128>> {
+    input
         .trim_matches('\n')
         .split_whitespace()
-        .map(|stone| Ok(stone.parse::<u64>()?))
-        .collect::<Result<Vec<_>>>()?;
+        .map(|stone| Ok(stone.parse::<u128>()?))
+        .collect()
+}
+
+pub fn solve_one(input: &str) -> Result<String> {
+    let mut stones = parse_input(input)?;
     for _ in 0..25 {
-        stones = stones.into_iter().flat_map(blink).collect();
+        stones = stones
+            .into_iter()
+            .map(blink)
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect();
     }
     Ok(stones.len().to_string())
 }
 
-fn blink(stone: u64) -> Vec<u64> {
-    if stone == 0 {
+fn blink(stone: u128) -> Result<Vec<u128>> {
+    Ok(if stone == 0 {
         vec![1]
     } else {
         let mut r = stone % 10;
@@ -30,39 +41,74 @@ fn blink(stone: u64) -> Vec<u64> {
         if l * 10 / tens != 0 {
             vec![l, r]
         } else {
-            vec![stone * 2024]
+            vec![stone
+                .checked_mul(2024)
+                .ok_or_else(|| anyhow!("stone {stone} overflows when multiplied by 2024"))?]
+        }
+    })
+}
+
+/// number of stones after blinking, erroring if the count overflows
+///
+/// blinks every distinct stone value once per blink, as [`blink_histogram`] does
+pub fn count_stones(input: &str, blinks: u32) -> Result<u128> {
+    let overflow = || anyhow!("stone count overflows after {blinks} blinks");
+    let mut counts = HashMap::<u128, u128>::new();
+    for stone in parse_input(input)? {
+        let count = counts.entry(stone).or_default();
+        *count = count.checked_add(1).ok_or_else(overflow)?;
+    }
+    for _ in 0..blinks {
+        let mut next = HashMap::<u128, u128>::new();
+        for (stone, count) in counts {
+            for s in blink(stone)? {
+                let c = next.entry(s).or_default();
+                *c = c.checked_add(count).ok_or_else(overflow)?;
+            }
         }
+        counts = next;
     }
+    counts
+        .into_values()
+        .try_fold(0u128, |sum, count| sum.checked_add(count))
+        .ok_or_else(overflow)
 }
 
 pub fn solve_two(input: &str) -> Result<String> {
-    let stones = input
-        .trim_matches('\n')
-        .split_whitespace()
-        .map(|stone| Ok(stone.parse::<u64>()?))
-        .collect::<Result<Vec<_>>>()?;
-    let mut mem = HashMap::new();
-    Ok(stones
-        .into_iter()
-        .map(|stone| num_stones(&mut mem, stone, 75))
-        .sum::<usize>()
-        .to_string())
+    Ok(count_stones(input, 75)?.to_string())
 }
 
-fn num_stones(mem: &mut HashMap<(u64, u32), usize>, stone: u64, count: u32) -> usize {
-    if count == 0 {
-        return 1;
-    }
-    if let Some(&num) = mem.get(&(stone, count)) {
-        return num;
+/// stones after blinking as a count of each stone value
+pub struct Histogram {
+    pub counts: HashMap<u128, BigUint>,
+    /// distinct stone values seen at any blink
+    pub reached: usize,
+}
+impl Histogram {
+    pub fn total(&self) -> BigUint {
+        self.counts.values().sum()
     }
-    let new_stones = blink(stone);
+}
 
-    let num = num_stones(mem, new_stones[0], count - 1)
-        + new_stones
-            .get(1)
-            .map(|&s| num_stones(mem, s, count - 1))
-            .unwrap_or(0);
-    mem.insert((stone, count), num);
-    num
+/// blink every distinct stone value once per blink, counts can not overflow
+pub fn blink_histogram(input: &str, blinks: u32) -> Result<Histogram> {
+    let mut counts = HashMap::<u128, BigUint>::new();
+    for stone in parse_input(input)? {
+        *counts.entry(stone).or_default() += 1u32;
+    }
+    let mut reached = counts.keys().copied().collect::<HashSet<_>>();
+    for _ in 0..blinks {
+        let mut next = HashMap::<u128, BigUint>::new();
+        for (stone, count) in counts {
+            for s in blink(stone)? {
+                reached.insert(s);
+                *next.entry(s).or_default() += &count;
+            }
+        }
+        counts = next;
+    }
+    Ok(Histogram {
+        counts,
+        reached: reached.len(),
+    })
 }
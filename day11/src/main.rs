@@ -1,6 +1,34 @@
+use anyhow::Result;
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day11, part1)]
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// count the stones after this many blinks instead of solving
+    #[arg(long)]
+    blinks: Option<u32>,
+    /// count with a histogram of stone values, also printing the distinct values
+    #[arg(long, requires = "blinks")]
+    histogram: bool,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        let Some(blinks) = self.blinks else {
+            return Ok(false);
+        };
+        if self.histogram {
+            let histogram = day11::blink_histogram(input, blinks)?;
+            println!("stones:\n{}", histogram.total());
+            println!("distinct values:\n{}", histogram.counts.len());
+            println!("distinct values reached:\n{}", histogram.reached);
+        } else {
+            println!("stones:\n{}", day11::count_stones(input, blinks)?);
+        }
+        Ok(true)
+    }
+}
+
+#[aoc(day11, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day11::solve_one(input)
 }
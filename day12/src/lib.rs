@@ -1,178 +1,193 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::BTreeSet;
+use std::fmt::Write;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 
-pub fn solve_one(input: &str) -> Result<String> {
-    let plots = input
-        .lines()
-        .map(|line| line.chars().collect::<Vec<char>>())
-        .collect::<Vec<Vec<char>>>();
-    let max_i_j = (plots.len(), plots[0].len());
-    let mut seen = vec![vec![false; max_i_j.1]; max_i_j.0];
+/// grid point on the corners of the plots as (row, column)
+type Vertex = (usize, usize);
 
-    Ok((0..max_i_j.0)
-        .flat_map(|i| (0..max_i_j.1).map(move |j| (i, j)))
-        .filter_map(|(i, j)| {
-            let region = area_perimeter(&plots, &mut seen, max_i_j, (i, j), plots[i][j]);
-            if region.0 == 0 {
-                None
-            } else {
-                Some(region.0 * region.1)
-            }
-        })
-        .sum::<u32>()
-        .to_string())
-}
+/// unit steps along plot edges, clockwise
+const DIRS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
 
-fn area_perimeter(
-    plots: &[Vec<char>],
-    seen: &mut [Vec<bool>],
-    max_i_j: (usize, usize),
-    i_j: (usize, usize),
-    last: char,
-) -> (u32, u32) {
-    if last != plots[i_j.0][i_j.1] {
-        return (0, 1);
-    }
-    if seen[i_j.0][i_j.1] {
-        return (0, 0);
-    }
-    seen[i_j.0][i_j.1] = true;
-    vec![
-        i_j.0.checked_sub(1).map(|i| (i, i_j.1)),
-        i_j.0
-            .checked_add(1)
-            .filter(|&i| i < max_i_j.0)
-            .map(|i| (i, i_j.1)),
-        i_j.1.checked_sub(1).map(|j| (i_j.0, j)),
-        i_j.1
-            .checked_add(1)
-            .filter(|&j| j < max_i_j.1)
-            .map(|j| (i_j.0, j)),
-    ]
-    .into_iter()
-    .map(|next| match next {
-        Some(i_j) => area_perimeter(plots, seen, max_i_j, i_j, last),
-        None => (0, 1),
-    })
-    .fold((1, 0), |acc, ap| (acc.0 + ap.0, acc.1 + ap.1))
+/// connected plots of one plant
+#[derive(Clone, Debug)]
+pub struct Region {
+    pub plant: char,
+    pub cells: Vec<(usize, usize)>,
+    pub area: u32,
+    pub perimeter: u32,
+    /// straight fence sections, the corners of all the boundaries
+    pub sides: u32,
+    /// regions of other plants enclosed by this one, as separate boundaries
+    pub holes: u32,
+    /// corners of the outer boundary clockwise, followed by each hole's anticlockwise
+    pub boundaries: Vec<Vec<Vertex>>,
 }
 
-pub fn solve_two(input: &str) -> Result<String> {
+pub fn parse_input(input: &str) -> Result<Vec<Vec<char>>> {
     let plots = input
         .lines()
         .map(|line| line.chars().collect::<Vec<char>>())
         .collect::<Vec<Vec<char>>>();
-    let max_i_j = (plots.len(), plots[0].len());
-    let mut seen = vec![vec![false; max_i_j.1]; max_i_j.0];
-
-    Ok((0..max_i_j.0)
-        .flat_map(|i| (0..max_i_j.1).map(move |j| (i, j)))
-        .filter_map(|(i, j)| {
-            let region = area_edges(&plots, &mut seen, max_i_j, (i, j), (i, j), plots[i][j]);
-            if region.0 == 0 {
-                None
-            } else {
-                Some(
-                    region.0
-                        * region
-                            .1
-                            .into_iter()
-                            .map(|(i, j, dir)| match dir {
-                                Dir::N | Dir::S => ((dir, i), j),
-                                Dir::E | Dir::W => ((dir, j), i),
-                            })
-                            .fold(HashMap::new(), |mut map, (k, v)| {
-                                map.entry(k)
-                                    .and_modify(|vec: &mut Vec<usize>| vec.push(v))
-                                    .or_insert_with(|| vec![v]);
-                                map
-                            })
-                            .into_values()
-                            .map(|mut v| {
-                                v.sort();
-                                1 + v.windows(2).filter(|w| w[0].abs_diff(w[1]) > 1).count() as u32
-                            })
-                            .sum::<u32>(),
-                )
-            }
-        })
-        .sum::<u32>()
-        .to_string())
+    if plots.is_empty() || plots.iter().any(|l| l.len() != plots[0].len()) {
+        bail!("garden is not a non-empty rectangle");
+    }
+    Ok(plots)
 }
 
-#[derive(Eq, PartialEq, Hash)]
-enum Dir {
-    N,
-    S,
-    E,
-    W,
+fn neighbours(
+    (height, width): (usize, usize),
+    (i, j): (usize, usize),
+) -> impl Iterator<Item = Option<(usize, usize)>> {
+    DIRS.into_iter().map(move |(di, dj)| {
+        let (i, j) = (i.checked_add_signed(di)?, j.checked_add_signed(dj)?);
+        (i < height && j < width).then_some((i, j))
+    })
 }
-impl Dir {
-    fn of(from: (usize, usize), to: (usize, usize)) -> Self {
-        match from.0.cmp(&to.0) {
-            std::cmp::Ordering::Less => Self::S,
-            std::cmp::Ordering::Equal => {
-                if from.1 < to.1 {
-                    Self::E
-                } else {
-                    Self::W
+
+/// every region of the garden, in row-major order of their first plot
+pub fn regions(plots: &[Vec<char>]) -> Vec<Region> {
+    let size = (plots.len(), plots[0].len());
+    let mut ids = vec![vec![None; size.1]; size.0];
+    let mut regions = vec![];
+    for (i, j) in (0..size.0).flat_map(|i| (0..size.1).map(move |j| (i, j))) {
+        if ids[i][j].is_some() {
+            continue;
+        }
+        let (id, plant) = (regions.len(), plots[i][j]);
+        ids[i][j] = Some(id);
+        let mut cells = vec![];
+        let mut stack = vec![(i, j)];
+        while let Some(cell) = stack.pop() {
+            cells.push(cell);
+            for (ni, nj) in neighbours(size, cell).flatten() {
+                if plots[ni][nj] == plant && ids[ni][nj].is_none() {
+                    ids[ni][nj] = Some(id);
+                    stack.push((ni, nj));
                 }
             }
-            std::cmp::Ordering::Greater => Self::N,
         }
+        regions.push(region(plant, cells, &ids, size));
     }
+    regions
 }
-fn area_edges(
-    plots: &[Vec<char>],
-    seen: &mut [Vec<bool>],
-    max_i_j: (usize, usize),
-    i_j: (usize, usize),
-    last_i_j: (usize, usize),
-    last: char,
-) -> (u32, HashSet<(usize, usize, Dir)>) {
-    if last != plots[i_j.0][i_j.1] {
-        let mut edge = HashSet::new();
-        edge.insert((last_i_j.0, last_i_j.1, Dir::of(last_i_j, i_j)));
-        return (0, edge);
+
+fn region(
+    plant: char,
+    mut cells: Vec<(usize, usize)>,
+    ids: &[Vec<Option<usize>>],
+    size: (usize, usize),
+) -> Region {
+    cells.sort();
+    let id = ids[cells[0].0][cells[0].1];
+    // fence edges with the region on their right, so the outside runs clockwise
+    let mut edges = BTreeSet::new();
+    for (i, j) in &cells {
+        let corners = [(*i, *j), (*i, j + 1), (i + 1, j + 1), (i + 1, *j)];
+        for (d, next) in neighbours(size, (*i, *j)).enumerate() {
+            if next.is_none_or(|(ni, nj)| ids[ni][nj] != id) {
+                edges.insert((corners[d], corners[(d + 1) % 4]));
+            }
+        }
     }
-    if seen[i_j.0][i_j.1] {
-        return (0, HashSet::new());
+    let perimeter = edges.len() as u32;
+    let mut boundaries = vec![];
+    // the smallest vertex left is always a corner, so each boundary starts on one
+    while let Some(start) = edges.pop_first() {
+        boundaries.push(trace(start, &mut edges));
     }
-    seen[i_j.0][i_j.1] = true;
-    vec![
-        i_j.0
-            .checked_sub(1)
-            .map(|i| (Some((i, i_j.1)), Dir::N))
-            .unwrap_or_else(|| (None, Dir::N)),
-        i_j.0
-            .checked_add(1)
-            .filter(|&i| i < max_i_j.0)
-            .map(|i| (Some((i, i_j.1)), Dir::S))
-            .unwrap_or_else(|| (None, Dir::S)),
-        i_j.1
-            .checked_sub(1)
-            .map(|j| (Some((i_j.0, j)), Dir::W))
-            .unwrap_or_else(|| (None, Dir::W)),
-        i_j.1
-            .checked_add(1)
-            .filter(|&j| j < max_i_j.1)
-            .map(|j| (Some((i_j.0, j)), Dir::E))
-            .unwrap_or_else(|| (None, Dir::E)),
-    ]
-    .into_iter()
-    .map(|(next, dir)| match next {
-        Some(next_i_j) => area_edges(plots, seen, max_i_j, next_i_j, i_j, last),
-        None => {
-            let mut edge = HashSet::new();
-            edge.insert((i_j.0, i_j.1, dir));
-            (0, edge)
-        }
-    })
-    .fold((1, HashSet::new()), |(area, mut edges), ae| {
-        ae.1.into_iter().for_each(|e| {
-            edges.insert(e);
+    Region {
+        plant,
+        area: cells.len() as u32,
+        cells,
+        perimeter,
+        sides: boundaries.iter().map(|b| b.len() as u32).sum(),
+        holes: boundaries.len() as u32 - 1,
+        boundaries,
+    }
+}
+
+fn dir_of((from, to): (Vertex, Vertex)) -> usize {
+    let step = (
+        to.0 as isize - from.0 as isize,
+        to.1 as isize - from.1 as isize,
+    );
+    DIRS.iter().position(|d| *d == step).expect("unit edge")
+}
+
+/// corners of the boundary through `start`, removing its edges
+///
+/// where the region touches itself diagonally the leftmost turn is taken, so the outside on
+/// either side of the touch gets its own boundary
+fn trace(start: (Vertex, Vertex), edges: &mut BTreeSet<(Vertex, Vertex)>) -> Vec<Vertex> {
+    let mut corners = vec![start.0];
+    let (mut at, mut dir) = (start.1, dir_of(start));
+    loop {
+        let next = [3, 0, 1].into_iter().find_map(|turn| {
+            let d = (dir + turn) % 4;
+            let to = (
+                at.0.checked_add_signed(DIRS[d].0)?,
+                at.1.checked_add_signed(DIRS[d].1)?,
+            );
+            ((at, to) == start || edges.contains(&(at, to))).then_some((at, to))
         });
-        (area + ae.0, edges)
-    })
+        let Some(edge) = next.filter(|edge| *edge != start) else {
+            return corners;
+        };
+        edges.remove(&edge);
+        if dir_of(edge) != dir {
+            corners.push(at);
+        }
+        (at, dir) = (edge.1, dir_of(edge));
+    }
+}
+
+/// garden as svg with each region filled in its own colour and outlined
+pub fn to_svg(plots: &[Vec<char>], regions: &[Region], cell: usize) -> String {
+    let (height, width) = (plots.len() * cell, plots[0].len() * cell);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\">\n"
+    );
+    for (id, region) in regions.iter().enumerate() {
+        let path = region
+            .boundaries
+            .iter()
+            .map(|b| {
+                let points = b
+                    .iter()
+                    .map(|(i, j)| format!("{} {}", j * cell, i * cell))
+                    .collect::<Vec<_>>();
+                format!("M {} Z", points.join(" L "))
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        // golden angle steps keep neighbouring regions apart in hue
+        let hue = (id as f64 * 137.508) % 360.0;
+        writeln!(
+            svg,
+            "<path d=\"{path}\" fill=\"hsl({hue:.0}, 65%, 60%)\" fill-rule=\"evenodd\" \
+             stroke=\"black\"><title>{} area {} sides {}</title></path>",
+            region.plant, region.area, region.sides
+        )
+        .unwrap();
+    }
+    svg += "</svg>\n";
+    svg
+}
+
+pub fn solve_one(input: &str) -> Result<String> {
+    Ok(regions(&parse_input(input)?)
+        .iter()
+        .map(|r| r.area as u64 * r.perimeter as u64)
+        .sum::<u64>()
+        .to_string())
+}
+
+pub fn solve_two(input: &str) -> Result<String> {
+    Ok(regions(&parse_input(input)?)
+        .iter()
+        .map(|r| r.area as u64 * r.sides as u64)
+        .sum::<u64>()
+        .to_string())
 }
@@ -1,6 +1,46 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day12, part1)]
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// print each region's plant, area, perimeter, sides and holes instead of solving
+    #[arg(long)]
+    regions: bool,
+    /// write the garden as an svg coloured by region instead of solving
+    #[arg(long, value_name = "FILE")]
+    svg: Option<PathBuf>,
+    /// pixels per plot in the svg
+    #[arg(long, default_value_t = 10)]
+    cell: usize,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        if !self.regions && self.svg.is_none() {
+            return Ok(false);
+        }
+        let plots = day12::parse_input(input)?;
+        let regions = day12::regions(&plots);
+        if self.regions {
+            for r in &regions {
+                let (i, j) = r.cells[0];
+                println!(
+                    "{} at ({i},{j}): area {} perimeter {} sides {} holes {}",
+                    r.plant, r.area, r.perimeter, r.sides, r.holes
+                );
+            }
+        }
+        if let Some(file) = &self.svg {
+            fs::write(file, day12::to_svg(&plots, &regions, self.cell))?;
+        }
+        Ok(true)
+    }
+}
+
+#[aoc(day12, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day12::solve_one(input)
 }
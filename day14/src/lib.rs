@@ -1,12 +1,10 @@
-use std::collections::HashSet;
+use anyhow::{anyhow, bail, Result};
 
-use anyhow::{anyhow, Result};
+pub const ROOM: (i32, i32) = (101, 103);
 
-const ROOM: (i32, i32) = (101, 103);
+pub type Robot = ((i32, i32), (i32, i32));
 
-type Robot = ((i32, i32), (i32, i32));
-
-fn parse_input(input: &str) -> Result<Vec<Robot>> {
+pub fn parse_input(input: &str) -> Result<Vec<Robot>> {
     input
         .lines()
         .map(|s| {
@@ -22,15 +20,14 @@ fn parse_input(input: &str) -> Result<Vec<Robot>> {
         .collect::<Result<Vec<_>>>()
 }
 
-fn positions(robots: &[Robot], secs: i32) -> Vec<(i32, i32)> {
+pub fn positions(robots: &[Robot], secs: i32) -> Vec<(i32, i32)> {
+    // i64 holds the product of any two i32, so no number of seconds overflows
+    let axis = |p: i32, v: i32, room: i32| {
+        (p as i64 + secs as i64 * v as i64).rem_euclid(room as i64) as i32
+    };
     robots
         .iter()
-        .map(|(p, v)| {
-            (
-                (p.0 + (secs * v.0)).rem_euclid(ROOM.0),
-                (p.1 + (secs * v.1)).rem_euclid(ROOM.1),
-            )
-        })
+        .map(|(p, v)| (axis(p.0, v.0, ROOM.0), axis(p.1, v.1, ROOM.1)))
         .collect()
 }
 
@@ -56,39 +53,90 @@ pub fn solve_one(input: &str) -> Result<String> {
         .to_string())
 }
 
-pub fn solve_two(input: &str) -> Result<String> {
-    let robots = parse_input(input)?;
+/// variance of the values times the count squared, kept as an integer
+fn spread(values: impl Iterator<Item = i32>) -> i64 {
+    let (n, sum, sum_sq) = values.fold((0i64, 0i64, 0i64), |(n, sum, sum_sq), v| {
+        (n + 1, sum + v as i64, sum_sq + (v as i64).pow(2))
+    });
+    n * sum_sq - sum.pow(2)
+}
+
+/// second in `0..period` where the robots are least spread on one axis
+fn least_spread(robots: &[Robot], period: i32, axis: fn(&(i32, i32)) -> i32) -> i32 {
+    (0..period)
+        .min_by_key(|secs| spread(positions(robots, *secs).iter().map(axis)))
+        .unwrap_or(0)
+}
 
-    let mut secs = 0;
-    let mut tree = false;
-    while !tree && secs < 50000 {
-        secs += 1;
-        let pos = positions(&robots, secs).into_iter().collect::<HashSet<_>>();
+/// `t` with `t = a1 mod n1` and `t = a2 mod n2`, for coprime `n1` and `n2`
+fn crt(a1: i64, n1: i64, a2: i64, n2: i64) -> Option<i64> {
+    // extended euclid for the inverse of n1 mod n2
+    let (mut r0, mut r1, mut s0, mut s1) = (n1.rem_euclid(n2), n2, 1, 0);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, s0 - q * s1);
+    }
+    if r0 != 1 {
+        return None;
+    }
+    Some(a1 + n1 * ((a2 - a1) * s0).rem_euclid(n2))
+}
 
-        let isolated = pos
-            .iter()
-            .filter(|p| {
-                !pos.contains(&(p.0 + 1, p.1))
-                    && !pos.contains(&(p.0 - 1, p.1))
-                    && !pos.contains(&(p.0, p.1 + 1))
-                    && !pos.contains(&(p.0, p.1 - 1))
-            })
-            .count();
+/// second the robots form the easter egg, when x and y are both least spread
+///
+/// x positions repeat every `ROOM.0` seconds and y every `ROOM.1`, so each axis is checked
+/// over its period and the seconds combined
+pub fn easter_egg(robots: &[Robot]) -> Result<i32> {
+    let x_secs = least_spread(robots, ROOM.0, |pos| pos.0);
+    let y_secs = least_spread(robots, ROOM.1, |pos| pos.1);
+    crt(x_secs as i64, ROOM.0 as i64, y_secs as i64, ROOM.1 as i64)
+        .map(|secs| secs as i32)
+        .ok_or_else(|| anyhow!("room dimensions {ROOM:?} are not coprime"))
+}
 
-        if isolated < pos.len() / 2 {
-            tree = true;
-            // print pos
-            //let mut r = vec![vec![' '; ROOM.0 as usize]; ROOM.1 as usize];
-            //pos.iter()
-            //    .for_each(|&(i, j)| r[j as usize][i as usize] = '#');
-            //r.iter().for_each(|l| {
-            //    l.iter().for_each(|c| print!("{c}"));
-            //    println!("");
-            //});
-        }
+/// robots at a second, each row is a y
+pub fn render(robots: &[Robot], secs: i32) -> Vec<Vec<bool>> {
+    let mut grid = vec![vec![false; ROOM.0 as usize]; ROOM.1 as usize];
+    for (x, y) in positions(robots, secs) {
+        grid[y as usize][x as usize] = true;
     }
-    if !tree {
-        anyhow::bail!("couldnt find tree after {secs} secs");
+    grid
+}
+
+pub fn to_text(grid: &[Vec<bool>]) -> String {
+    grid.iter()
+        .map(|l| {
+            l.iter()
+                .map(|r| if *r { '#' } else { '.' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// plain pbm image, robots are black
+pub fn to_pbm(grid: &[Vec<bool>]) -> String {
+    let mut pbm = format!(
+        "P1\n{} {}\n",
+        grid.first().map_or(0, |l| l.len()),
+        grid.len()
+    );
+    for l in grid {
+        pbm += &l
+            .iter()
+            .map(|r| if *r { "1" } else { "0" })
+            .collect::<Vec<_>>()
+            .join(" ");
+        pbm += "\n";
+    }
+    pbm
+}
+
+pub fn solve_two(input: &str) -> Result<String> {
+    let robots = parse_input(input)?;
+    if robots.is_empty() {
+        bail!("no robots");
     }
-    Ok(secs.to_string())
+    Ok(easter_egg(&robots)?.to_string())
 }
@@ -1,6 +1,41 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day14, part1)]
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// render the robots after this many seconds instead of solving
+    #[arg(long, value_name = "SECONDS")]
+    render: Option<i32>,
+    /// render the robots when they form the easter egg instead of solving
+    #[arg(long, conflicts_with = "render")]
+    egg: bool,
+    /// write the render as a pbm image instead of printing it
+    #[arg(long, value_name = "FILE")]
+    image: Option<PathBuf>,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        let robots = day14::parse_input(input)?;
+        let secs = match self.render {
+            Some(secs) => secs,
+            None if self.egg => day14::easter_egg(&robots)?,
+            None => return Ok(false),
+        };
+        let grid = day14::render(&robots, secs);
+        match &self.image {
+            Some(file) => fs::write(file, day14::to_pbm(&grid))?,
+            None => println!("{}", day14::to_text(&grid)),
+        }
+        println!("after {secs} seconds");
+        Ok(true)
+    }
+}
+
+#[aoc(day14, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day14::solve_one(input)
 }
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 
 use anyhow::{anyhow, bail, Result};
 
@@ -17,21 +17,13 @@ enum Dir {
     R,
 }
 impl Dir {
-    fn next(&self, pos: (usize, usize)) -> (usize, usize) {
-        match self {
-            Dir::U => (pos.0 - 1, pos.1),
+    fn next(&self, pos: (usize, usize)) -> Option<(usize, usize)> {
+        Some(match self {
+            Dir::U => (pos.0.checked_sub(1)?, pos.1),
             Dir::D => (pos.0 + 1, pos.1),
-            Dir::L => (pos.0, pos.1 - 1),
+            Dir::L => (pos.0, pos.1.checked_sub(1)?),
             Dir::R => (pos.0, pos.1 + 1),
-        }
-    }
-    fn op(&self) -> Self {
-        match self {
-            Dir::U => Dir::D,
-            Dir::D => Dir::U,
-            Dir::L => Dir::R,
-            Dir::R => Dir::L,
-        }
+        })
     }
 }
 struct Input {
@@ -90,163 +82,228 @@ fn parse_input(input: &str) -> Result<Input> {
 }
 
 pub fn solve_one(input: &str) -> Result<String> {
-    let Input {
-        mut map,
-        mut robot_pos,
-        movement,
-    } = parse_input(input)?;
-
-    for dir in movement {
-        move_robot(dir, &mut map, &mut robot_pos)?;
-    }
-
-    Ok(map
-        .into_iter()
-        .enumerate()
-        .flat_map(|(i, l)| l.into_iter().enumerate().map(move |(j, m)| (i, j, m)))
-        .map(|(i, j, m)| if m == Map::Box { (100 * i) + j } else { 0 })
-        .sum::<usize>()
-        .to_string())
+    let mut sim = Simulation::new(input, 1)?;
+    while sim.step() {}
+    Ok(sim.gps().to_string())
 }
 
-fn move_robot(dir: Dir, map: &mut [Vec<Map>], robot_pos: &mut (usize, usize)) -> Result<()> {
-    let mut empty = None;
-    let mut next = dir.next(*robot_pos);
-    while empty.is_none() && map[next.0][next.1] != Map::Wall {
-        if map[next.0][next.1] == Map::Empty {
-            empty = Some(next);
-        }
-        next = dir.next(next);
-    }
-    if let Some(mut empty) = empty {
-        let op = dir.op();
-        let mut robot_moved = false;
-        let mut next = op.next(empty);
-        while !robot_moved {
-            match map[next.0][next.1] {
-                Map::Wall => bail!("unexpected wall {next:?}"),
-                Map::Box => {
-                    map[empty.0][empty.1] = Map::Box;
-                    map[next.0][next.1] = Map::Empty;
-                    empty = op.next(empty);
-                }
-                Map::Robot => {
-                    map[empty.0][empty.1] = Map::Robot;
-                    map[next.0][next.1] = Map::Empty;
-                    robot_moved = true;
-                    *robot_pos = empty;
-                }
-                Map::Empty => {}
-            }
-            next = op.next(next);
-        }
-    }
-    Ok(())
+pub fn solve_two(input: &str) -> Result<String> {
+    let mut sim = Simulation::new(input, 2)?;
+    while sim.step() {}
+    Ok(sim.gps().to_string())
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-enum WideMap {
-    Wall,
-    BoxL,
-    BoxR,
-    Robot,
-    Empty,
+/// warehouse with the map scaled horizontally, boxes are `width` cells wide
+struct Warehouse {
+    walls: Vec<Vec<bool>>,
+    /// left cell of each box
+    boxes: Vec<(usize, usize)>,
+    /// box covering each cell
+    cells: Vec<Vec<Option<usize>>>,
+    width: usize,
+    robot_pos: (usize, usize),
 }
-pub fn solve_two(input: &str) -> Result<String> {
-    let Input { map, movement, .. } = parse_input(input)?;
-    let mut map = map
-        .into_iter()
-        .map(|l| {
-            l.into_iter()
-                .flat_map(|m| match m {
-                    Map::Wall => vec![WideMap::Wall, WideMap::Wall],
-                    Map::Box => vec![WideMap::BoxL, WideMap::BoxR],
-                    Map::Robot => vec![WideMap::Robot, WideMap::Empty],
-                    Map::Empty => vec![WideMap::Empty, WideMap::Empty],
+impl Warehouse {
+    fn new(map: &[Vec<Map>], robot_pos: (usize, usize), scale: usize) -> Self {
+        let mut warehouse = Self {
+            walls: map
+                .iter()
+                .map(|l| {
+                    l.iter()
+                        .flat_map(|m| vec![*m == Map::Wall; scale])
+                        .collect()
                 })
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<Vec<_>>>();
-    let mut robot_pos = {
-        let mut pos = None;
+                .collect(),
+            boxes: vec![],
+            cells: map.iter().map(|l| vec![None; l.len() * scale]).collect(),
+            width: scale,
+            robot_pos: (robot_pos.0, robot_pos.1 * scale),
+        };
         for (i, l) in map.iter().enumerate() {
             for (j, m) in l.iter().enumerate() {
-                if *m == WideMap::Robot {
-                    if pos.is_some() {
-                        bail!("multiple robots in map");
-                    }
-                    pos = Some((i, j));
+                if *m == Map::Box {
+                    warehouse.boxes.push((i, j * scale));
+                    warehouse.place(warehouse.boxes.len() - 1);
                 }
             }
         }
-        pos.ok_or_else(|| anyhow!("no robot in map"))?
-    };
+        warehouse
+    }
 
-    for dir in movement {
-        move_wide_robot(dir, &mut map, &mut robot_pos)?;
+    fn is_wall(&self, pos: (usize, usize)) -> bool {
+        self.walls
+            .get(pos.0)
+            .and_then(|l| l.get(pos.1))
+            .is_none_or(|wall| *wall)
     }
 
-    Ok(map
-        .into_iter()
-        .enumerate()
-        .flat_map(|(i, l)| l.into_iter().enumerate().map(move |(j, m)| (i, j, m)))
-        .map(
-            |(i, j, m)| {
-                if m == WideMap::BoxL {
-                    (100 * i) + j
-                } else {
-                    0
+    fn box_cells(&self, b: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (i, j) = self.boxes[b];
+        (j..j + self.width).map(move |j| (i, j))
+    }
+
+    fn place(&mut self, b: usize) {
+        for (i, j) in self.box_cells(b).collect::<Vec<_>>() {
+            self.cells[i][j] = Some(b);
+        }
+    }
+
+    /// move the robot, pushing every box in contact with it in the direction, unless any of
+    /// them would hit a wall
+    fn move_robot(&mut self, dir: &Dir) {
+        let mut pushed = HashSet::new();
+        let mut check = VecDeque::from([self.robot_pos]);
+        while let Some(pos) = check.pop_front() {
+            let Some(next) = dir.next(pos).filter(|next| !self.is_wall(*next)) else {
+                return;
+            };
+            if let Some(b) = self.cells[next.0][next.1] {
+                if pushed.insert(b) {
+                    check.extend(self.box_cells(b).filter(|cell| {
+                        dir.next(*cell)
+                            .is_none_or(|c| self.cells[c.0].get(c.1) != Some(&Some(b)))
+                    }));
                 }
-            },
-        )
-        .sum::<usize>()
-        .to_string())
+            }
+        }
+        for b in &pushed {
+            for (i, j) in self.box_cells(*b).collect::<Vec<_>>() {
+                self.cells[i][j] = None;
+            }
+        }
+        for b in pushed {
+            // checked for walls above, so in bounds
+            if let Some(next) = dir.next(self.boxes[b]) {
+                self.boxes[b] = next;
+                self.place(b);
+            }
+        }
+        if let Some(next) = dir.next(self.robot_pos) {
+            self.robot_pos = next;
+        }
+    }
+
+    fn grid(&self) -> Vec<Vec<char>> {
+        let mut grid = self
+            .walls
+            .iter()
+            .map(|l| {
+                l.iter()
+                    .map(|w| if *w { '#' } else { '.' })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for b in 0..self.boxes.len() {
+            for (n, (i, j)) in self.box_cells(b).enumerate() {
+                grid[i][j] = match (self.width, n) {
+                    (1, _) => 'O',
+                    (_, 0) => '[',
+                    (w, n) if n == w - 1 => ']',
+                    _ => '=',
+                };
+            }
+        }
+        grid[self.robot_pos.0][self.robot_pos.1] = '@';
+        grid
+    }
 }
 
-fn move_wide_robot(
-    dir: Dir,
-    map: &mut Vec<Vec<WideMap>>,
-    robot_pos: &mut (usize, usize),
-) -> Result<()> {
-    let mut new_map = map.clone();
-    let mut from = HashSet::new();
-    let mut to = HashSet::new();
-    if move_wide(&dir, map, &mut new_map, *robot_pos, &mut from, &mut to)? {
-        from.difference(&to)
-            .for_each(|&(i, j)| new_map[i][j] = WideMap::Empty);
-        std::mem::swap(map, &mut new_map);
-        *robot_pos = dir.next(*robot_pos);
-    }
-    Ok(())
+/// robot moving boxes around the warehouse one move at a time
+pub struct Simulation {
+    warehouse: Warehouse,
+    movement: Vec<Dir>,
+    step: usize,
 }
+impl Simulation {
+    /// simulation of the input map scaled horizontally, 1 for part one and 2 for part two
+    pub fn new(input: &str, scale: usize) -> Result<Self> {
+        if scale == 0 {
+            bail!("scale must be at least 1");
+        }
+        let Input {
+            map,
+            robot_pos,
+            movement,
+        } = parse_input(input)?;
+        Ok(Self {
+            warehouse: Warehouse::new(&map, robot_pos, scale),
+            movement,
+            step: 0,
+        })
+    }
 
-fn move_wide(
-    dir: &Dir,
-    map: &[Vec<WideMap>],
-    new_map: &mut [Vec<WideMap>],
-    pos: (usize, usize),
-    from: &mut HashSet<(usize, usize)>,
-    to: &mut HashSet<(usize, usize)>,
-) -> Result<bool> {
-    let next = dir.next(pos);
-    from.insert(pos);
-    to.insert(next);
-    new_map[next.0][next.1] = map[pos.0][pos.1].clone();
-    Ok(match map[next.0][next.1] {
-        WideMap::Wall => false,
-        WideMap::BoxL => {
-            move_wide(dir, map, new_map, next, from, to)?
-                && (*dir == Dir::L
-                    || *dir == Dir::R
-                    || move_wide(dir, map, new_map, Dir::R.next(next), from, to)?)
+    /// make the next move, false if there are none left
+    pub fn step(&mut self) -> bool {
+        let Some(dir) = self.movement.get(self.step).cloned() else {
+            return false;
+        };
+        self.warehouse.move_robot(&dir);
+        self.step += 1;
+        true
+    }
+
+    /// moves made so far
+    pub fn steps(&self) -> usize {
+        self.step
+    }
+
+    /// moves in total
+    pub fn len(&self) -> usize {
+        self.movement.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.movement.is_empty()
+    }
+
+    /// sum of the box GPS coordinates
+    pub fn gps(&self) -> usize {
+        self.warehouse.boxes.iter().map(|(i, j)| 100 * i + j).sum()
+    }
+
+    /// map in the puzzle's text form, boxes wider than 2 are drawn `[==]`
+    pub fn grid(&self) -> Vec<Vec<char>> {
+        self.warehouse.grid()
+    }
+
+    pub fn frame(&self) -> Frame {
+        Frame {
+            step: self.step,
+            grid: self.grid(),
         }
-        WideMap::BoxR => {
-            move_wide(dir, map, new_map, next, from, to)?
-                && (*dir == Dir::L
-                    || *dir == Dir::R
-                    || move_wide(dir, map, new_map, Dir::L.next(next), from, to)?)
+    }
+}
+impl Iterator for Simulation {
+    type Item = Frame;
+
+    /// make the next move and return the map after it
+    fn next(&mut self) -> Option<Self::Item> {
+        self.step().then(|| self.frame())
+    }
+}
+
+/// map after a number of moves
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub step: usize,
+    pub grid: Vec<Vec<char>>,
+}
+impl Frame {
+    pub fn to_text(&self) -> String {
+        self.grid
+            .iter()
+            .map(|l| l.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// colour of a map cell in image frames
+    pub fn colour(c: &char) -> [u8; 3] {
+        match c {
+            '#' => [128, 128, 128],
+            'O' | '[' | ']' | '=' => [181, 123, 64],
+            '@' => [220, 40, 40],
+            _ => [0, 0, 0],
         }
-        WideMap::Robot => bail!("unexpected robot"),
-        WideMap::Empty => true,
-    })
+    }
 }
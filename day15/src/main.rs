@@ -1,6 +1,83 @@
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day15, part1)]
+#[derive(Clone, Debug, clap::ValueEnum)]
+enum Format {
+    Text,
+    Ppm,
+}
+
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// write the map after every --every moves to this directory instead of solving
+    #[arg(long, value_name = "DIR")]
+    frames: Option<PathBuf>,
+    /// frame file format
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// pixels per map cell in ppm frames
+    #[arg(long, default_value_t = 4)]
+    cell: usize,
+    /// animate the map in the terminal instead of solving
+    #[arg(long)]
+    animate: bool,
+    /// milliseconds between animation frames
+    #[arg(long, default_value_t = 50)]
+    delay: u64,
+    /// write or animate every nth move
+    #[arg(long, default_value_t = 1)]
+    every: usize,
+    /// scale the map horizontally, 2 for the part two wide map
+    #[arg(long, default_value_t = 1)]
+    scale: usize,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        if self.frames.is_none() && !self.animate {
+            return Ok(false);
+        }
+        let mut sim = day15::Simulation::new(input, self.scale)?;
+        let len = sim.len();
+        let mut frame = sim.frame();
+        loop {
+            if frame.step % self.every.max(1) == 0 || frame.step == len {
+                if let Some(dir) = &self.frames {
+                    let (ext, data) = match self.format {
+                        Format::Text => ("txt", (frame.to_text() + "\n").into_bytes()),
+                        Format::Ppm => (
+                            "ppm",
+                            utils::ppm::from_grid(&frame.grid, self.cell, day15::Frame::colour),
+                        ),
+                    };
+                    fs::create_dir_all(dir)?;
+                    fs::write(dir.join(format!("{:06}.{ext}", frame.step)), data)?;
+                }
+                if self.animate {
+                    print!(
+                        "\x1b[H\x1b[2J{}\nmove {}/{len}\n",
+                        frame.to_text(),
+                        frame.step
+                    );
+                    thread::sleep(Duration::from_millis(self.delay));
+                }
+            }
+            match sim.next() {
+                Some(next) => frame = next,
+                None => break,
+            }
+        }
+        println!("GPS sum: {}", sim.gps());
+        Ok(true)
+    }
+}
+
+#[aoc(day15, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day15::solve_one(input)
 }
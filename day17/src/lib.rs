@@ -1,20 +1,219 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
 use anyhow::{anyhow, bail, Result};
 
-enum CompStep {
+/// combo operand, 0-3 are literal values, 4-6 read the registers
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Combo {
+    Lit(u64),
+    A,
+    B,
+    C,
+}
+impl Combo {
+    pub fn new(operand: u64) -> Result<Self> {
+        Ok(match operand {
+            0..=3 => Self::Lit(operand),
+            4 => Self::A,
+            5 => Self::B,
+            6 => Self::C,
+            7 => bail!("invalid combo operand 7"),
+            _ => bail!("unexpected combo operand {operand}"),
+        })
+    }
+    pub fn operand(&self) -> u64 {
+        match self {
+            Self::Lit(v) => *v,
+            Self::A => 4,
+            Self::B => 5,
+            Self::C => 6,
+        }
+    }
+}
+impl fmt::Display for Combo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lit(v) => write!(f, "{v}"),
+            Self::A => write!(f, "A"),
+            Self::B => write!(f, "B"),
+            Self::C => write!(f, "C"),
+        }
+    }
+}
+
+/// decoded instruction
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Instr {
+    Adv(Combo),
+    Bxl(u64),
+    Bst(Combo),
+    Jnz(u64),
+    /// operand is read but ignored
+    Bxc(u64),
+    Out(Combo),
+    Bdv(Combo),
+    Cdv(Combo),
+}
+impl Instr {
+    pub fn decode(opcode: u64, operand: u64) -> Result<Self> {
+        let literal = || match operand {
+            0..=7 => Ok(operand),
+            _ => bail!("unexpected literal operand {operand}"),
+        };
+        Ok(match opcode {
+            0 => Self::Adv(Combo::new(operand)?),
+            1 => Self::Bxl(literal()?),
+            2 => Self::Bst(Combo::new(operand)?),
+            3 => Self::Jnz(literal()?),
+            4 => Self::Bxc(literal()?),
+            5 => Self::Out(Combo::new(operand)?),
+            6 => Self::Bdv(Combo::new(operand)?),
+            7 => Self::Cdv(Combo::new(operand)?),
+            _ => bail!("unexpected opcode {opcode}"),
+        })
+    }
+    pub fn encode(&self) -> [u64; 2] {
+        match self {
+            Self::Adv(c) => [0, c.operand()],
+            Self::Bxl(v) => [1, *v],
+            Self::Bst(c) => [2, c.operand()],
+            Self::Jnz(v) => [3, *v],
+            Self::Bxc(v) => [4, *v],
+            Self::Out(c) => [5, c.operand()],
+            Self::Bdv(c) => [6, c.operand()],
+            Self::Cdv(c) => [7, c.operand()],
+        }
+    }
+}
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Adv(c) => write!(f, "adv {c}"),
+            Self::Bxl(v) => write!(f, "bxl {v}"),
+            Self::Bst(c) => write!(f, "bst {c}"),
+            Self::Jnz(v) => write!(f, "jnz {v}"),
+            Self::Bxc(0) => write!(f, "bxc"),
+            Self::Bxc(v) => write!(f, "bxc {v}"),
+            Self::Out(c) => write!(f, "out {c}"),
+            Self::Bdv(c) => write!(f, "bdv {c}"),
+            Self::Cdv(c) => write!(f, "cdv {c}"),
+        }
+    }
+}
+
+/// disassemble a program, one `ptr: instr` line per instruction
+pub fn disassemble(program: &[u64]) -> Result<String> {
+    program
+        .chunks(2)
+        .enumerate()
+        .map(|(i, instr)| match instr {
+            [opcode, operand] => Ok(format!(
+                "{:>3}: {}",
+                i * 2,
+                Instr::decode(*opcode, *operand)?
+            )),
+            _ => bail!("missing operand at {}", i * 2),
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(|lines| lines.join("\n"))
+}
+
+/// assemble mnemonic source into a program
+///
+/// one instruction per line, e.g. `bst A` or `jnz start`, a line can start with a `label:`
+/// and `;` starts a comment. combo operands are `0`-`3` or `A`/`B`/`C`, literal operands are
+/// `0`-`7` and `jnz` also takes a label. a numeric label must match the pointer of the
+/// instruction, so the output of [`disassemble`] assembles back to the same program
+pub fn assemble(source: &str) -> Result<Vec<u64>> {
+    let mut labels = HashMap::new();
+    let mut instrs = vec![];
+    for (n, line) in source.lines().enumerate() {
+        let mut line = line.split(';').next().unwrap_or_default().trim();
+        while let Some((label, rest)) = line.split_once(':') {
+            let label = label.trim();
+            let ptr = instrs.len() * 2;
+            if let Ok(label_ptr) = label.parse::<usize>() {
+                if label_ptr != ptr {
+                    bail!(
+                        "line {}: label {label_ptr} does not match pointer {ptr}",
+                        n + 1
+                    );
+                }
+            } else if labels.insert(label, ptr).is_some() {
+                bail!("line {}: duplicate label '{label}'", n + 1);
+            }
+            line = rest.trim();
+        }
+        if !line.is_empty() {
+            instrs.push((n + 1, line));
+        }
+    }
+
+    let mut program = Vec::with_capacity(instrs.len() * 2);
+    for (n, line) in instrs {
+        let instr = parse_instr(line, &labels).map_err(|e| anyhow!("line {n}: {e}"))?;
+        program.extend(instr.encode());
+    }
+    Ok(program)
+}
+fn parse_instr(line: &str, labels: &HashMap<&str, usize>) -> Result<Instr> {
+    let (mnemonic, operand) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let operand = operand.trim();
+    Ok(match mnemonic {
+        "adv" => Instr::Adv(combo(operand)?),
+        "bxl" => Instr::Bxl(literal(operand)?),
+        "bst" => Instr::Bst(combo(operand)?),
+        "jnz" => Instr::Jnz(match labels.get(operand) {
+            Some(ptr) if *ptr < 8 => *ptr as u64,
+            Some(ptr) => bail!("label '{operand}' at {ptr} is out of jnz range"),
+            None if operand.parse::<u64>().is_ok() => literal(operand)?,
+            None => bail!("unknown label '{operand}'"),
+        }),
+        "bxc" if operand.is_empty() => Instr::Bxc(0),
+        "bxc" => Instr::Bxc(literal(operand)?),
+        "out" => Instr::Out(combo(operand)?),
+        "bdv" => Instr::Bdv(combo(operand)?),
+        "cdv" => Instr::Cdv(combo(operand)?),
+        _ => bail!("unknown mnemonic '{mnemonic}'"),
+    })
+}
+fn literal(operand: &str) -> Result<u64> {
+    match operand.parse::<u64>() {
+        Ok(v) if v < 8 => Ok(v),
+        _ => bail!("expected literal operand 0-7, got '{operand}'"),
+    }
+}
+fn combo(operand: &str) -> Result<Combo> {
+    match operand {
+        "A" => Ok(Combo::A),
+        "B" => Ok(Combo::B),
+        "C" => Ok(Combo::C),
+        _ => match operand.parse::<u64>() {
+            Ok(v) if v < 4 => Ok(Combo::Lit(v)),
+            Ok(7) => bail!("invalid combo operand 7"),
+            _ => bail!("expected combo operand 0-3 or A/B/C, got '{operand}'"),
+        },
+    }
+}
+
+pub enum CompStep {
     Continue,
     Halt,
     Output(u64),
 }
 
-struct Comp {
-    a: u64,
-    b: u64,
-    c: u64,
-    program: Vec<u64>,
-    ptr: usize,
+#[derive(Clone, Debug)]
+pub struct Comp {
+    pub a: u64,
+    pub b: u64,
+    pub c: u64,
+    pub program: Vec<u64>,
+    pub ptr: usize,
 }
 impl Comp {
-    fn output(&mut self) -> Result<Vec<u64>> {
+    pub fn output(&mut self) -> Result<Vec<u64>> {
         let mut out = vec![];
         loop {
             match self.step()? {
@@ -26,77 +225,129 @@ impl Comp {
             }
         }
     }
-    fn step(&mut self) -> Result<CompStep> {
-        Ok(
-            match (self.program.get(self.ptr), self.program.get(self.ptr + 1)) {
-                (None, None) => CompStep::Halt,
-                (Some(0), Some(operand)) => {
-                    self.a /= 1 << self.combo_operand(*operand)?;
-                    self.ptr += 2;
-                    CompStep::Continue
-                }
-                (Some(1), Some(operand)) => {
-                    self.b ^= operand;
-                    self.ptr += 2;
-                    CompStep::Continue
-                }
-                (Some(2), Some(operand)) => {
-                    self.b = self.combo_operand(*operand)? % 8;
-                    self.ptr += 2;
-                    CompStep::Continue
-                }
-                (Some(3), Some(operand)) => {
-                    if self.a == 0 {
-                        self.ptr += 2;
-                    } else {
-                        self.ptr = *operand as usize;
-                    }
-                    CompStep::Continue
-                }
-                (Some(4), Some(_)) => {
-                    self.b ^= self.c;
-                    self.ptr += 2;
-                    CompStep::Continue
-                }
-                (Some(5), Some(operand)) => {
-                    self.ptr += 2;
-                    CompStep::Output(self.combo_operand(*operand)? % 8)
-                }
-                (Some(6), Some(operand)) => {
-                    self.b = self.a / (1 << self.combo_operand(*operand)?);
-                    self.ptr += 2;
-                    CompStep::Continue
-                }
-                (Some(7), Some(operand)) => {
-                    self.c = self.a / (1 << self.combo_operand(*operand)?);
-                    self.ptr += 2;
-                    CompStep::Continue
-                }
-                op => bail!("unexpected opcode operand: {op:?}"),
-            },
-        )
+
+    /// output, erroring if the program does not halt within `max_steps` instructions
+    pub fn output_with_limit(&mut self, max_steps: usize) -> Result<Vec<u64>> {
+        let mut out = vec![];
+        for _ in 0..max_steps {
+            match self.step()? {
+                CompStep::Continue => {}
+                CompStep::Halt => return Ok(out),
+                CompStep::Output(output) => out.push(output),
+            }
+        }
+        bail!("no halt within {max_steps} steps")
     }
-    fn combo_operand(&self, operand: u64) -> Result<u64> {
-        Ok(match operand {
-            0 => 0,
-            1 => 1,
-            2 => 2,
-            3 => 3,
-            4 => self.a,
-            5 => self.b,
-            6 => self.c,
-            7 => bail!("7 combo op"),
-            _ => bail!("unexpected combo operand {operand}"),
+
+    /// run until halted, calling `on_step` before each instruction is executed
+    pub fn trace(&mut self, mut on_step: impl FnMut(&Comp, Instr)) -> Result<Vec<u64>> {
+        let mut out = vec![];
+        while let Some(instr) = self.instr()? {
+            on_step(self, instr);
+            if let CompStep::Output(output) = self.step()? {
+                out.push(output);
+            }
+        }
+        Ok(out)
+    }
+
+    /// run until the pointer reaches one of `breakpoints` or the program halts, pushing any
+    /// output to `out`, returns true if stopped on a breakpoint
+    ///
+    /// at least one instruction is run so this can be called again to continue
+    pub fn run_to_break(&mut self, breakpoints: &[usize], out: &mut Vec<u64>) -> Result<bool> {
+        loop {
+            match self.step()? {
+                CompStep::Continue => {}
+                CompStep::Halt => return Ok(false),
+                CompStep::Output(output) => out.push(output),
+            }
+            if breakpoints.contains(&self.ptr) {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// instruction at the pointer, none if halted
+    pub fn instr(&self) -> Result<Option<Instr>> {
+        match (self.program.get(self.ptr), self.program.get(self.ptr + 1)) {
+            (Some(opcode), Some(operand)) => Ok(Some(Instr::decode(*opcode, *operand)?)),
+            (None, _) => Ok(None),
+            op => bail!("unexpected opcode operand: {op:?}"),
+        }
+    }
+
+    pub fn step(&mut self) -> Result<CompStep> {
+        let Some(instr) = self.instr()? else {
+            return Ok(CompStep::Halt);
+        };
+        self.ptr += 2;
+        Ok(match instr {
+            Instr::Adv(c) => {
+                self.a = self.dv(c);
+                CompStep::Continue
+            }
+            Instr::Bxl(v) => {
+                self.b ^= v;
+                CompStep::Continue
+            }
+            Instr::Bst(c) => {
+                self.b = self.combo(c) % 8;
+                CompStep::Continue
+            }
+            Instr::Jnz(v) => {
+                if self.a != 0 {
+                    self.ptr = v as usize;
+                }
+                CompStep::Continue
+            }
+            Instr::Bxc(_) => {
+                self.b ^= self.c;
+                CompStep::Continue
+            }
+            Instr::Out(c) => CompStep::Output(self.combo(c) % 8),
+            Instr::Bdv(c) => {
+                self.b = self.dv(c);
+                CompStep::Continue
+            }
+            Instr::Cdv(c) => {
+                self.c = self.dv(c);
+                CompStep::Continue
+            }
         })
     }
+    /// A divided by 2 to the power of the combo operand
+    fn dv(&self, combo: Combo) -> u64 {
+        u32::try_from(self.combo(combo))
+            .ok()
+            .and_then(|shift| self.a.checked_shr(shift))
+            .unwrap_or(0)
+    }
+    fn combo(&self, combo: Combo) -> u64 {
+        match combo {
+            Combo::Lit(v) => v,
+            Combo::A => self.a,
+            Combo::B => self.b,
+            Combo::C => self.c,
+        }
+    }
 
-    fn reset_with_a(&mut self, a: u64) {
+    pub fn reset_with_a(&mut self, a: u64) {
         self.a = a;
         self.ptr = 0;
     }
 }
+impl fmt::Display for Comp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ptr={:<3} A={:<20} B={:<20} C={}",
+            self.ptr, self.a, self.b, self.c
+        )
+    }
+}
 
-fn parse_input(input: &str) -> Result<Comp> {
+pub fn parse_input(input: &str) -> Result<Comp> {
     let mut lines = input.lines();
     Ok(Comp {
         a: lines
@@ -135,43 +386,326 @@ pub fn solve_one(input: &str) -> Result<String> {
         .join(","))
 }
 
-pub fn solve_two(input: &str) -> Result<String> {
-    let mut comp = parse_input(input)?;
-    let mut pos_a = (1..=8).collect::<Vec<_>>();
-    let mut full_match = false;
-    while !full_match && !pos_a.is_empty() {
-        pos_a = pos_a
-            .into_iter()
-            .flat_map(|last_a| (0..8).map(move |rem| (last_a * 8) + rem))
-            .filter_map(|a| {
-                comp.reset_with_a(a);
-                match comp.output() {
-                    Ok(output) => match output.len().cmp(&comp.program.len()) {
-                        std::cmp::Ordering::Less => {
-                            if comp.program.ends_with(&output) {
-                                Some(Ok(a))
-                            } else {
-                                None
-                            }
-                        }
-                        std::cmp::Ordering::Equal => {
-                            if output == comp.program {
-                                full_match = true;
-                                Some(Ok(a))
-                            } else {
-                                None
-                            }
-                        }
-                        std::cmp::Ordering::Greater => None,
-                    },
-                    Err(e) => Some(Err(anyhow!(e))),
-                }
+/// shape of a program that loops on A, see [`analyse`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LoopShape {
+    /// bits shifted out of A per loop iteration
+    pub shift: u32,
+    /// outputs per loop iteration
+    pub outputs: usize,
+}
+
+/// largest shift per iteration the search will enumerate
+const MAX_SHIFT: u32 = 16;
+/// shifts the fallback search tries when the loop does not shift A by a constant
+const FALLBACK_SHIFTS: RangeInclusive<u32> = 1..=3;
+/// bounds for the fallback search
+const MAX_STEPS: usize = 100_000;
+const MAX_CANDIDATES: usize = 1 << 20;
+
+/// value of a register during one pass of the loop, in terms of A at the start of the pass
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Sym {
+    Const(u64),
+    /// A shifted right by a constant number of bits
+    Shr(u32),
+    /// any other function of A
+    OfA,
+    /// depends on B or C from before the pass
+    Carried,
+}
+impl Sym {
+    fn combine(self, other: Self, f: impl FnOnce(u64, u64) -> u64) -> Self {
+        match (self, other) {
+            (Self::Carried, _) | (_, Self::Carried) => Self::Carried,
+            (Self::Const(x), Self::Const(y)) => Self::Const(f(x, y)),
+            _ => Self::OfA,
+        }
+    }
+    fn shr(self, bits: Self) -> Self {
+        match (self, bits) {
+            (Self::Shr(s), Self::Const(n)) if u64::from(s) + n < 64 => Self::Shr(s + n as u32),
+            (Self::Shr(_), Self::Const(_)) => Self::Const(0),
+            _ => self.combine(bits, |x, n| {
+                u32::try_from(n)
+                    .ok()
+                    .and_then(|n| x.checked_shr(n))
+                    .unwrap_or(0)
+            }),
+        }
+    }
+    fn low_bits(self) -> Self {
+        self.combine(Self::Const(8), |x, m| x % m)
+    }
+}
+
+/// one pass through the body of a program ending in `jnz 0`
+struct LoopTrace {
+    /// A at the end of the pass
+    a: Sym,
+    /// values output during the pass
+    outputs: Vec<Sym>,
+}
+impl LoopTrace {
+    fn new(program: &[u64]) -> Result<Self> {
+        let instrs = program
+            .chunks(2)
+            .map(|instr| match instr {
+                [opcode, operand] => Instr::decode(*opcode, *operand),
+                _ => bail!("missing operand"),
             })
-            .collect::<Result<_>>()?
+            .collect::<Result<Vec<_>>>()?;
+        let Some((Instr::Jnz(0), body)) = instrs.split_last() else {
+            bail!("program does not end with 'jnz 0'");
+        };
+
+        let (mut a, mut b, mut c) = (Sym::Shr(0), Sym::Carried, Sym::Carried);
+        let mut outputs = vec![];
+        for instr in body {
+            let value = move |combo: &Combo| match combo {
+                Combo::Lit(v) => Sym::Const(*v),
+                Combo::A => a,
+                Combo::B => b,
+                Combo::C => c,
+            };
+            match instr {
+                Instr::Adv(op) => a = a.shr(value(op)),
+                Instr::Bxl(v) => b = b.combine(Sym::Const(*v), |x, y| x ^ y),
+                Instr::Bst(op) => b = value(op).low_bits(),
+                Instr::Jnz(_) => bail!("more than one jump in the program"),
+                Instr::Bxc(_) => b = b.combine(c, |x, y| x ^ y),
+                Instr::Out(op) => outputs.push(value(op).low_bits()),
+                Instr::Bdv(op) => b = a.shr(value(op)),
+                Instr::Cdv(op) => c = a.shr(value(op)),
+            }
+        }
+        Ok(Self { a, outputs })
+    }
+
+    /// bits shifted out of A per pass
+    fn shift(&self) -> Result<u32> {
+        match self.a {
+            Sym::Shr(0) => bail!("A is not shifted in the loop"),
+            Sym::Shr(shift) if shift > MAX_SHIFT => {
+                bail!("A is shifted by {shift} bits per loop, more than {MAX_SHIFT}")
+            }
+            Sym::Shr(shift) => Ok(shift),
+            a => bail!("A is not shifted by a constant in the loop, ends as {a:?}"),
+        }
+    }
+}
+
+/// check the program is a single loop `jnz 0` on A, where each iteration shifts A right by a
+/// constant number of bits and every output only depends on A at the start of the iteration
+///
+/// the loop body is evaluated symbolically, following each register as a constant, A shifted
+/// by a constant, some other function of A, or something carried over in B or C from the
+/// iteration before. A at the start of the last iteration is less than `1 << shift`, so A
+/// can be found a window of `shift` bits at a time working back from the last output
+pub fn analyse(program: &[u64]) -> Result<LoopShape> {
+    let trace = LoopTrace::new(program)?;
+    let shift = trace.shift()?;
+    if let Some(i) = trace.outputs.iter().position(|v| *v == Sym::Carried) {
+        bail!(
+            "output {} of the loop depends on B or C from the previous loop",
+            i + 1
+        );
+    }
+    let outputs = trace.outputs.len();
+    if outputs == 0 || !program.len().is_multiple_of(outputs) {
+        bail!(
+            "{outputs} outputs per loop does not divide program length {}",
+            program.len()
+        );
+    }
+    Ok(LoopShape { shift, outputs })
+}
+
+/// find the lowest A using the loop shape, building A up `shape.shift` bits per loop
+fn search_shape(comp: &mut Comp, shape: LoopShape) -> Result<Option<u64>> {
+    let program = comp.program.clone();
+    let (b, c) = (comp.b, comp.c);
+    let mut pos_a: Vec<u64> = vec![0];
+    for loops in 1..=program.len() / shape.outputs {
+        let expected = &program[program.len() - loops * shape.outputs..];
+        let mut next = vec![];
+        for a in pos_a
+            .into_iter()
+            .filter(|a| a.leading_zeros() >= shape.shift)
+            .flat_map(|last_a| (0..1 << shape.shift).map(move |low| (last_a << shape.shift) | low))
+        {
+            (comp.b, comp.c) = (b, c);
+            comp.reset_with_a(a);
+            if comp.output()? == expected {
+                next.push(a);
+            }
+        }
+        pos_a = next;
     }
     Ok(pos_a
         .into_iter()
-        .min()
-        .ok_or_else(|| anyhow!("no possible a"))?
-        .to_string())
+        .filter(|a| *a != 0 || program.is_empty())
+        .min())
+}
+
+/// depth first search adding `shift` bits to A at a time, keeping A when the output is a
+/// suffix of the program, bounded by steps per run and total candidates tried
+fn search_bounded(comp: &mut Comp, shift: u32) -> Result<Option<u64>> {
+    let program = comp.program.clone();
+    let (b, c) = (comp.b, comp.c);
+    let mut tried = 0;
+    let mut stack = (1..1 << shift).rev().collect::<Vec<u64>>();
+    let mut best = None;
+    while let Some(a) = stack.pop() {
+        tried += 1;
+        if tried > MAX_CANDIDATES {
+            break;
+        }
+        (comp.b, comp.c) = (b, c);
+        comp.reset_with_a(a);
+        let Ok(output) = comp.output_with_limit(MAX_STEPS) else {
+            continue;
+        };
+        if output == program {
+            best = Some(best.map_or(a, |best: u64| best.min(a)));
+        } else if output.len() < program.len()
+            && program.ends_with(&output)
+            && a.leading_zeros() >= shift
+        {
+            stack.extend((0..1 << shift).rev().map(|low| (a << shift) | low));
+        }
+    }
+    Ok(best)
+}
+
+pub fn solve_two(input: &str) -> Result<String> {
+    let mut comp = parse_input(input)?;
+    let a = match analyse(&comp.program) {
+        Ok(shape) => search_shape(&mut comp, shape)?
+            .ok_or_else(|| anyhow!("no possible a for loop shape {shape:?}"))?,
+        Err(e) => {
+            let shifts = LoopTrace::new(&comp.program)
+                .and_then(|trace| trace.shift())
+                .map_or(FALLBACK_SHIFTS, |shift| shift..=shift);
+            let mut best = None;
+            for shift in shifts {
+                if let Some(a) = search_bounded(&mut comp, shift)? {
+                    best = Some(best.map_or(a, |best: u64| best.min(a)));
+                }
+            }
+            best.ok_or_else(|| {
+                anyhow!("program is not of a solvable form ({e}) and bounded search found no a")
+            })?
+        }
+    };
+    Ok(a.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let programs: [&[u64]; 5] = [
+            &[0, 1, 5, 4, 3, 0],
+            &[0, 3, 5, 4, 3, 0],
+            &[2, 4, 1, 1, 7, 5, 1, 5, 4, 0, 0, 3, 5, 5, 3, 0],
+            &[0, 6, 1, 7, 2, 5, 3, 2, 4, 3, 5, 6, 6, 4, 7, 0],
+            &[4, 0, 4, 5, 3, 6],
+        ];
+        for program in programs {
+            let source = disassemble(program).unwrap();
+            assert_eq!(assemble(&source).unwrap(), program, "{source}");
+        }
+        for program in [[1, 8], [3, 8], [4, 9], [5, 8]] {
+            assert!(disassemble(&program).is_err(), "{program:?}");
+        }
+    }
+
+    #[test]
+    fn labels() {
+        let source = "
+            ; shift a down three bits at a time until it is zero
+            start: adv 3
+            out A ; print it
+            jnz start
+        ";
+        assert_eq!(assemble(source).unwrap(), [0, 3, 5, 4, 3, 0]);
+        assert_eq!(
+            assemble("  0: adv 1\n  2: out A\n  4: jnz 0").unwrap(),
+            [0, 1, 5, 4, 3, 0]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = |source: &str| assemble(source).unwrap_err().to_string();
+        assert!(err("adv 7").contains("invalid combo operand 7"));
+        assert!(err("out 7").contains("invalid combo operand 7"));
+        assert!(err("jnz nowhere").contains("unknown label"));
+        assert!(err("a: adv 1\na: out A").contains("duplicate label"));
+        assert!(err("2: adv 1").contains("does not match pointer"));
+        assert!(disassemble(&[0, 7]).is_err());
+    }
+
+    fn input(program: &[u64]) -> String {
+        let program = program
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        format!("Register A: 0\nRegister B: 0\nRegister C: 0\n\nProgram: {program}\n")
+    }
+
+    #[test]
+    fn shapes() {
+        let shape = |shift, outputs| LoopShape { shift, outputs };
+        assert_eq!(
+            analyse(&[2, 4, 1, 1, 7, 5, 1, 5, 4, 0, 0, 3, 5, 5, 3, 0]).unwrap(),
+            shape(3, 1)
+        );
+        // shifted by B holding a constant, and C is read before it is set but never output
+        assert_eq!(
+            analyse(&[2, 2, 0, 5, 4, 6, 0, 2, 5, 4, 3, 0]).unwrap(),
+            shape(4, 1)
+        );
+        assert_eq!(
+            analyse(&[0, 3, 7, 4, 5, 4, 2, 6, 0, 3, 5, 4, 3, 0]).unwrap(),
+            shape(6, 2)
+        );
+
+        let err = |program: &[u64]| analyse(program).unwrap_err().to_string();
+        assert!(err(&[6, 1, 0, 2, 4, 5, 5, 5, 3, 0]).contains("previous loop"));
+        assert!(err(&[0, 4, 5, 4, 3, 0]).contains("not shifted by a constant"));
+        assert!(err(&[5, 4, 3, 0]).contains("not shifted in the loop"));
+        assert!(err(&[0, 3, 5, 4, 3, 2]).contains("does not end with"));
+    }
+
+    #[test]
+    fn quines() {
+        let quines: [(&[u64], u64); 4] = [
+            (&[0, 3, 5, 4, 3, 0], 117440),
+            // adv B with B = 2 then adv 2, four bits per loop
+            (&[2, 2, 0, 5, 4, 6, 0, 2, 5, 4, 3, 0], 57526897148448),
+            // two outputs per loop
+            (&[0, 3, 7, 4, 5, 4, 2, 6, 0, 3, 5, 4, 3, 0], 1970422337216),
+            // output depends on C so only the bounded search, two bits at a time
+            (&[6, 1, 0, 2, 4, 5, 5, 5, 3, 0], 436492),
+        ];
+        for (program, a) in quines {
+            let input = input(program);
+            assert_eq!(solve_two(&input).unwrap(), a.to_string(), "{program:?}");
+            let mut comp = parse_input(&input).unwrap();
+            comp.reset_with_a(a);
+            assert_eq!(comp.output().unwrap(), program);
+        }
+
+        let mut comp = parse_input(&input(&[0, 3, 7, 4, 5, 4, 2, 6, 0, 3, 5, 4, 3, 0])).unwrap();
+        let shape = LoopShape {
+            shift: 6,
+            outputs: 2,
+        };
+        assert_eq!(search_shape(&mut comp, shape).unwrap(), Some(1970422337216));
+    }
 }
@@ -1,6 +1,55 @@
+use anyhow::Result;
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day17, part1)]
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// print the program disassembly instead of solving
+    #[arg(long)]
+    disasm: bool,
+    /// run the program printing the registers before every instruction
+    #[arg(long)]
+    trace: bool,
+    /// run the program printing the registers whenever the pointer reaches this value
+    #[arg(long = "break", value_name = "PTR")]
+    breakpoints: Vec<usize>,
+    /// override register A for --trace and --break
+    #[arg(long, value_name = "A")]
+    reg_a: Option<u64>,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        let mut comp = day17::parse_input(input)?;
+        if let Some(a) = self.reg_a {
+            comp.reset_with_a(a);
+        }
+        if self.disasm {
+            println!("{}", day17::disassemble(&comp.program)?);
+        }
+        if self.trace {
+            let out = comp.trace(|comp, instr| println!("{comp}  {instr}"))?;
+            println!("{comp}  halt");
+            println!("output: {}", join(&out));
+        } else if !self.breakpoints.is_empty() {
+            let mut out = vec![];
+            while comp.run_to_break(&self.breakpoints, &mut out)? {
+                println!("{comp}  output: {}", join(&out));
+            }
+            println!("{comp}  halt");
+            println!("output: {}", join(&out));
+        }
+        Ok(self.disasm || self.trace || !self.breakpoints.is_empty())
+    }
+}
+
+fn join(out: &[u64]) -> String {
+    out.iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[aoc(day17, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day17::solve_one(input)
 }
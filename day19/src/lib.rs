@@ -1,9 +1,9 @@
-use std::collections::HashMap;
+use std::fmt;
 
 use anyhow::{anyhow, bail, Result};
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-enum Colour {
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Colour {
     White,
     Blue,
     Black,
@@ -22,17 +22,173 @@ impl Colour {
         })
     }
 }
+impl fmt::Display for Colour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let c = match self {
+            Self::White => 'w',
+            Self::Blue => 'u',
+            Self::Black => 'b',
+            Self::Red => 'r',
+            Self::Green => 'g',
+        };
+        write!(f, "{c}")
+    }
+}
+
+const COLOURS: usize = 5;
+
+/// trie node, children are indexes into the trie with 0 (the root) for none
+#[derive(Clone, Debug, Default)]
+struct Node {
+    children: [usize; COLOURS],
+    towel: Option<usize>,
+}
+
+/// available towel patterns as a trie over colours
+#[derive(Clone, Debug)]
+pub struct Towels {
+    patterns: Vec<Vec<Colour>>,
+    nodes: Vec<Node>,
+}
+impl Towels {
+    pub fn new(patterns: Vec<Vec<Colour>>) -> Self {
+        let mut nodes = vec![Node::default()];
+        for (towel, pattern) in patterns.iter().enumerate() {
+            let mut node = 0;
+            for c in pattern {
+                if nodes[node].children[*c as usize] == 0 {
+                    nodes.push(Node::default());
+                    nodes[node].children[*c as usize] = nodes.len() - 1;
+                }
+                node = nodes[node].children[*c as usize];
+            }
+            // an empty or repeated pattern adds no new arrangements
+            if node != 0 {
+                nodes[node].towel.get_or_insert(towel);
+            }
+        }
+        Self { patterns, nodes }
+    }
+
+    /// colours of towel `towel`
+    pub fn pattern(&self, towel: usize) -> &[Colour] {
+        &self.patterns[towel]
+    }
+
+    /// towels matching the design at `start`, with their lengths
+    fn matches<'a>(
+        &'a self,
+        design: &'a [Colour],
+        start: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        design[start..]
+            .iter()
+            .scan(0, |node, c| {
+                *node = self.nodes[*node].children[*c as usize];
+                (*node != 0).then_some(*node)
+            })
+            .enumerate()
+            .filter_map(|(i, node)| self.nodes[node].towel.map(|towel| (towel, i + 1)))
+    }
+
+    /// fill `ways` with the number of arrangements of each suffix of the design, `ways[i]`
+    /// counting those of `design[i..]`
+    fn fill_ways(&self, design: &[Colour], ways: &mut Vec<u64>) {
+        ways.clear();
+        ways.resize(design.len() + 1, 0);
+        ways[design.len()] = 1;
+        for start in (0..design.len()).rev() {
+            ways[start] = self
+                .matches(design, start)
+                .fold(0, |n, (_, len)| n.saturating_add(ways[start + len]));
+        }
+    }
+
+    /// number of towel arrangements making the design, saturating at `u64::MAX`
+    ///
+    /// `ways` is scratch space kept between calls so counting does not allocate
+    pub fn count(&self, design: &[Colour], ways: &mut Vec<u64>) -> u64 {
+        self.fill_ways(design, ways);
+        ways[0]
+    }
+
+    /// every towel arrangement making the design, as towel indexes in order, found lazily
+    ///
+    /// as with [`Towels::count`], an empty design has one arrangement using no towels
+    pub fn arrangements<'a>(&'a self, design: &'a [Colour]) -> Arrangements<'a> {
+        let mut ways = vec![];
+        self.fill_ways(design, &mut ways);
+        let stack = if ways[0] > 0 {
+            vec![(0, self.matches(design, 0).collect())]
+        } else {
+            vec![]
+        };
+        Arrangements {
+            towels: self,
+            design,
+            ways,
+            stack,
+            towels_used: vec![],
+            empty: design.is_empty(),
+        }
+    }
+}
+
+/// iterator over the arrangements of a design, see [`Towels::arrangements`]
+pub struct Arrangements<'a> {
+    towels: &'a Towels,
+    design: &'a [Colour],
+    /// arrangements of each suffix, only suffixes that can be made are explored
+    ways: Vec<u64>,
+    /// position in the design and the matches there still to try
+    stack: Vec<(usize, Vec<(usize, usize)>)>,
+    towels_used: Vec<usize>,
+    /// the design is empty and its arrangement is still to be returned
+    empty: bool,
+}
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if std::mem::take(&mut self.empty) {
+            return Some(vec![]);
+        }
+        while let Some((pos, matches)) = self.stack.last_mut() {
+            let pos = *pos;
+            let Some((towel, len)) = matches.pop() else {
+                self.stack.pop();
+                self.towels_used.pop();
+                continue;
+            };
+            let next = pos + len;
+            if self.ways[next] == 0 {
+                continue;
+            }
+            if next == self.design.len() {
+                let mut arrangement = self.towels_used.clone();
+                arrangement.push(towel);
+                return Some(arrangement);
+            }
+            self.towels_used.push(towel);
+            self.stack
+                .push((next, self.towels.matches(self.design, next).collect()));
+        }
+        None
+    }
+}
 
-type Input = (Vec<Vec<Colour>>, Vec<Vec<Colour>>);
-fn parse_input(input: &str) -> Result<Input> {
+type Input = (Towels, Vec<Vec<Colour>>);
+pub fn parse_input(input: &str) -> Result<Input> {
     let (available, desired) = input
         .split_once("\n\n")
         .ok_or_else(|| anyhow!("no empty line"))?;
     Ok((
-        available
-            .split(", ")
-            .map(|towel| towel.chars().map(Colour::new).collect::<Result<Vec<_>>>())
-            .collect::<Result<Vec<Vec<_>>>>()?,
+        Towels::new(
+            available
+                .split(", ")
+                .map(|towel| towel.chars().map(Colour::new).collect::<Result<Vec<_>>>())
+                .collect::<Result<Vec<Vec<_>>>>()?,
+        ),
         desired
             .lines()
             .map(|towel| towel.chars().map(Colour::new).collect::<Result<Vec<_>>>())
@@ -40,57 +196,66 @@ fn parse_input(input: &str) -> Result<Input> {
     ))
 }
 
-fn is_possible(desired: &[Colour], available: &[Vec<Colour>]) -> bool {
-    if desired.is_empty() {
-        return true;
-    }
-    for a in available {
-        if desired.starts_with(a) && is_possible(&desired[a.len()..], available) {
-            return true;
-        }
-    }
-    false
-}
-
 pub fn solve_one(input: &str) -> Result<String> {
-    let (available, desired) = parse_input(input)?;
+    let (towels, desired) = parse_input(input)?;
 
+    let mut ways = vec![];
     Ok(desired
         .into_iter()
-        .filter(|d| is_possible(d, &available))
+        .filter(|d| towels.count(d, &mut ways) > 0)
         .count()
         .to_string())
 }
 
-fn count_possible(
-    mem: &mut HashMap<Vec<Colour>, u64>,
-    desired: &[Colour],
-    available: &[Vec<Colour>],
-) -> u64 {
-    if desired.is_empty() {
-        return 1;
-    }
-    if let Some(c) = mem.get(desired) {
-        return *c;
-    }
-    let mut c = 0;
-    for a in available {
-        if desired.starts_with(a) {
-            c += count_possible(mem, &desired[a.len()..], available)
-        }
-    }
-    mem.insert(desired.to_vec(), c);
-    c
-}
-
 pub fn solve_two(input: &str) -> Result<String> {
-    let (available, desired) = parse_input(input)?;
-
-    let mut mem = HashMap::new();
+    let (towels, desired) = parse_input(input)?;
 
+    let mut ways = vec![];
     Ok(desired
         .into_iter()
-        .map(|d| count_possible(&mut mem, &d, &available))
+        .map(|d| towels.count(&d, &mut ways))
         .sum::<u64>()
         .to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "r, wr, b, g, bwu, rb, gb, br
+
+brwrr
+bggr
+gbbr
+rrbgbr
+ubwu
+bwurrg
+brgr
+bbrgwb
+";
+
+    #[test]
+    fn arrangements_match_count() {
+        let (towels, desired) = parse_input(EXAMPLE).unwrap();
+        let mut ways = vec![];
+        for design in desired.iter().chain([&vec![]]) {
+            let arrangements = towels.arrangements(design).collect::<Vec<_>>();
+            assert_eq!(arrangements.len() as u64, towels.count(design, &mut ways));
+            for arrangement in arrangements {
+                let made = arrangement
+                    .iter()
+                    .flat_map(|towel| towels.pattern(*towel))
+                    .copied()
+                    .collect::<Vec<_>>();
+                assert_eq!(&made, design);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_design() {
+        let (towels, _) = parse_input(EXAMPLE).unwrap();
+        assert_eq!(towels.count(&[], &mut vec![]), 1);
+        assert_eq!(towels.arrangements(&[]).collect::<Vec<_>>(), [vec![]]);
+    }
+}
@@ -1,6 +1,46 @@
+use anyhow::Result;
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day19, part1)]
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// print the arrangements of towels making each design instead of solving
+    #[arg(long)]
+    explain: bool,
+    /// most arrangements to print per design
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    limit: usize,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        if !self.explain {
+            return Ok(false);
+        }
+        let (towels, desired) = day19::parse_input(input)?;
+        let mut ways = vec![];
+        for design in &desired {
+            let count = towels.count(design, &mut ways);
+            println!("{}: {count} arrangements", join(design));
+            for arrangement in towels.arrangements(design).take(self.limit) {
+                let patterns = arrangement
+                    .into_iter()
+                    .map(|towel| join(towels.pattern(towel)))
+                    .collect::<Vec<_>>();
+                println!("  {}", patterns.join(", "));
+            }
+            if count > self.limit as u64 {
+                println!("  ...");
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn join(colours: &[day19::Colour]) -> String {
+    colours.iter().map(|c| c.to_string()).collect()
+}
+
+#[aoc(day19, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day19::solve_one(input)
 }
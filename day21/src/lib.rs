@@ -1,267 +1,363 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
+use memo::Memo;
 
-static NUMERIC_KEYPAD: [[Option<Numeric>; 3]; 4] = [
-    [Some(Numeric::D7), Some(Numeric::D8), Some(Numeric::D9)],
-    [Some(Numeric::D4), Some(Numeric::D5), Some(Numeric::D6)],
-    [Some(Numeric::D1), Some(Numeric::D2), Some(Numeric::D3)],
-    [None, Some(Numeric::D0), Some(Numeric::A)],
-];
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-enum Numeric {
-    D0,
-    D1,
-    D2,
-    D3,
-    D4,
-    D5,
-    D6,
-    D7,
-    D8,
-    D9,
-    A,
+/// numeric keypad on the door, `#` is the gap
+pub const NUMERIC_KEYPAD: &str = "789\n456\n123\n#0A";
+/// directional keypad the robots are controlled with, `#` is the gap
+pub const DIR_KEYPAD: &str = "#^A\n<v>";
+
+const DIRS: [(char, (isize, isize)); 4] =
+    [('^', (-1, 0)), ('v', (1, 0)), ('<', (0, -1)), ('>', (0, 1))];
+
+/// keypad layout described as a text grid, one char per key with `#` for a gap
+#[derive(Clone, Debug)]
+pub struct Keypad {
+    keys: HashMap<char, (usize, usize)>,
+    cells: HashMap<(usize, usize), char>,
 }
-impl Numeric {
-    fn new(c: char) -> Result<Self> {
-        Ok(match c {
-            '0' => Numeric::D0,
-            '1' => Numeric::D1,
-            '2' => Numeric::D2,
-            '3' => Numeric::D3,
-            '4' => Numeric::D4,
-            '5' => Numeric::D5,
-            '6' => Numeric::D6,
-            '7' => Numeric::D7,
-            '8' => Numeric::D8,
-            '9' => Numeric::D9,
-            'A' => Numeric::A,
-            _ => bail!("unexpected char {c}"),
+impl Keypad {
+    pub fn new(grid: &str) -> Result<Self> {
+        let mut keys = HashMap::new();
+        for (i, line) in grid.lines().enumerate() {
+            for (j, c) in line.chars().enumerate() {
+                if c != '#' && keys.insert(c, (i, j)).is_some() {
+                    bail!("duplicate key '{c}'");
+                }
+            }
+        }
+        if !keys.contains_key(&'A') {
+            bail!("keypad has no 'A' key");
+        }
+        Ok(Self {
+            cells: keys.iter().map(|(c, pos)| (*pos, *c)).collect(),
+            keys,
         })
     }
-    fn seqs_to_press(&self, to: &Numeric) -> Vec<Vec<Dir>> {
-        let mut seqs = vec![(self.clone(), vec![])];
-        while seqs.iter().all(|(b, _)| b != to) {
-            seqs = seqs
+
+    fn pos(&self, key: char) -> Result<(usize, usize)> {
+        self.keys
+            .get(&key)
+            .copied()
+            .ok_or_else(|| anyhow!("no key '{key}' on keypad"))
+    }
+
+    /// every shortest sequence of directional presses moving from `from` to `to`, avoiding the
+    /// gap, followed by `A` to press it
+    pub fn seqs_to_press(&self, from: char, to: char) -> Result<Vec<Vec<char>>> {
+        self.seqs(self.pos(from)?, self.pos(to)?)
+            .ok_or_else(|| anyhow!("key '{to}' cannot be reached from '{from}'"))
+    }
+    /// shortest sequences found breadth first, `None` if the gap cuts `to` off from `from`
+    fn seqs(&self, from: (usize, usize), to: (usize, usize)) -> Option<Vec<Vec<char>>> {
+        let mut seen = HashSet::from([from]);
+        let mut paths = vec![(from, vec![])];
+        while !paths.iter().any(|(pos, _)| *pos == to) {
+            let next = paths
                 .iter()
-                .flat_map(|(b, s)| {
-                    b.dirs()
-                        .into_iter()
-                        .map(|(d, n)| {
-                            (n, {
-                                let mut s = s.clone();
-                                s.push(d);
-                                s
-                            })
+                .flat_map(|(pos, path)| {
+                    DIRS.iter().filter_map(|(d, (di, dj))| {
+                        let next = (
+                            pos.0.checked_add_signed(*di)?,
+                            pos.1.checked_add_signed(*dj)?,
+                        );
+                        (self.cells.contains_key(&next) && !seen.contains(&next)).then(|| {
+                            let mut path = path.clone();
+                            path.push(*d);
+                            (next, path)
                         })
-                        .collect::<Vec<_>>()
+                    })
                 })
-                .collect();
+                .collect::<Vec<_>>();
+            if next.is_empty() {
+                return None;
+            }
+            seen.extend(next.iter().map(|(pos, _)| *pos));
+            paths = next;
         }
-        seqs.into_iter()
-            .filter_map(|(b, mut s)| {
-                if b == *to {
-                    s.push(Dir::A);
-                    Some(s)
-                } else {
-                    None
-                }
-            })
-            .collect()
+        Some(
+            paths
+                .into_iter()
+                .filter(|(pos, _)| *pos == to)
+                .map(|(_, mut path)| {
+                    path.push('A');
+                    path
+                })
+                .collect(),
+        )
     }
-    fn dirs(&self) -> Vec<(Dir, Numeric)> {
-        let (i, j) = NUMERIC_KEYPAD
-            .iter()
-            .enumerate()
-            .flat_map(|(i, r)| r.iter().enumerate().map(move |(j, n)| (i, j, n)))
-            .find_map(|(i, j, n)| {
-                if n.as_ref() == Some(self) {
-                    Some((i, j))
-                } else {
-                    None
-                }
-            })
-            .unwrap();
-        vec![
-            i.checked_add(1)
-                .filter(|i| *i < NUMERIC_KEYPAD.len())
-                .map(|i| (i, j, Dir::D)),
-            i.checked_sub(1).map(|i| (i, j, Dir::U)),
-            j.checked_add(1)
-                .filter(|j| *j < NUMERIC_KEYPAD[i].len())
-                .map(|j| (i, j, Dir::R)),
-            j.checked_sub(1).map(|j| (i, j, Dir::L)),
-        ]
-        .into_iter()
-        .flatten()
-        .filter_map(|(i, j, d)| NUMERIC_KEYPAD[i][j].as_ref().map(|n| (d, n.clone())))
-        .collect()
+
+    /// keys pressed by a robot arm starting on `A` following the directional `presses`
+    pub fn replay(&self, presses: &[char]) -> Result<Vec<char>> {
+        let mut pos = self.pos('A')?;
+        let mut pressed = vec![];
+        for (i, press) in presses.iter().enumerate() {
+            if *press == 'A' {
+                pressed.push(self.cells[&pos]);
+                continue;
+            }
+            let (_, (di, dj)) = DIRS
+                .iter()
+                .find(|(d, _)| d == press)
+                .ok_or_else(|| anyhow!("unexpected press '{press}'"))?;
+            pos = pos
+                .0
+                .checked_add_signed(*di)
+                .zip(pos.1.checked_add_signed(*dj))
+                .filter(|next| self.cells.contains_key(next))
+                .ok_or_else(|| anyhow!("press {i} '{press}' moves off the keys"))?;
+        }
+        Ok(pressed)
     }
 }
 
-static DIR_KEYPAD: [[Option<Dir>; 3]; 2] = [
-    [None, Some(Dir::U), Some(Dir::A)],
-    [Some(Dir::L), Some(Dir::D), Some(Dir::R)],
-];
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-enum Dir {
-    U,
-    D,
-    L,
-    R,
-    A,
+/// presses longer than this are not built by [`Robots::presses`]
+const MAX_PRESSES: u64 = 1_000_000;
+
+/// chain of robots typing a code on `code_pad`, each controlled through a `dir_pad` by the
+/// next, with `depth` robots using directional keypads before the one pressed by hand
+pub struct Robots {
+    code_pad: Keypad,
+    dir_pad: Keypad,
+    depth: usize,
+    mem: Memo<(char, char, usize), u64>,
 }
-impl Dir {
-    fn seqs_to_press(&self, to: &Dir) -> Vec<Vec<Dir>> {
-        let mut seqs = vec![(self.clone(), vec![])];
-        while seqs.iter().all(|(b, _)| b != to) {
-            seqs = seqs
-                .iter()
-                .flat_map(|(b, s)| {
-                    b.dirs()
-                        .into_iter()
-                        .map(|(d, n)| {
-                            (n, {
-                                let mut s = s.clone();
-                                s.push(d);
-                                s
-                            })
-                        })
-                        .collect::<Vec<_>>()
-                })
-                .collect();
+impl Robots {
+    pub fn new(code_pad: Keypad, dir_pad: Keypad, depth: usize) -> Result<Self> {
+        // every press a robot can be asked to make must be reachable, so the searches below
+        // always find a sequence
+        let presses = DIRS
+            .iter()
+            .map(|(d, _)| *d)
+            .chain(['A'])
+            .collect::<Vec<_>>();
+        for from in &presses {
+            for to in &presses {
+                dir_pad.seqs_to_press(*from, *to)?;
+            }
         }
-        seqs.into_iter()
-            .filter_map(|(b, mut s)| {
-                if b == *to {
-                    s.push(Dir::A);
-                    Some(s)
-                } else {
-                    None
-                }
-            })
-            .collect()
+        Ok(Self {
+            code_pad,
+            dir_pad,
+            depth,
+            mem: Memo::new(),
+        })
     }
 
-    fn dirs(&self) -> Vec<(Dir, Dir)> {
-        let (i, j) = DIR_KEYPAD
-            .iter()
-            .enumerate()
-            .flat_map(|(i, r)| r.iter().enumerate().map(move |(j, n)| (i, j, n)))
-            .find_map(|(i, j, n)| {
-                if n.as_ref() == Some(self) {
-                    Some((i, j))
-                } else {
-                    None
-                }
-            })
-            .unwrap();
-        vec![
-            i.checked_add(1)
-                .filter(|i| *i < DIR_KEYPAD.len())
-                .map(|i| (i, j, Dir::D)),
-            i.checked_sub(1).map(|i| (i, j, Dir::U)),
-            j.checked_add(1)
-                .filter(|j| *j < DIR_KEYPAD[i].len())
-                .map(|j| (i, j, Dir::R)),
-            j.checked_sub(1).map(|j| (i, j, Dir::L)),
-        ]
-        .into_iter()
-        .flatten()
-        .filter_map(|(i, j, d)| DIR_KEYPAD[i][j].as_ref().map(|n| (d, n.clone())))
-        .collect()
+    /// fewest presses by hand to type the code
+    pub fn min_presses(&mut self, code: &str) -> Result<u64> {
+        let mut last = 'A';
+        let mut len = 0;
+        for c in code.chars() {
+            // first robot, code keypad
+            let dir_seqs = self.code_pad.seqs_to_press(last, c)?;
+
+            // me and remaining robots, dir keypad
+            len += min_length_seq(&self.dir_pad, &mut self.mem, &dir_seqs, self.depth);
+
+            last = c;
+        }
+        Ok(len)
     }
-}
 
-fn parse_input(input: &str) -> Result<Vec<(Vec<Numeric>, u64)>> {
-    input
-        .lines()
-        .map(|line| {
-            let mut n = 0;
-            line.chars()
-                .map(|c| {
-                    if let Some(d) = c.to_digit(10) {
-                        n *= 10;
-                        n += d as u64;
-                    }
-                    Numeric::new(c)
-                })
-                .collect::<Result<Vec<_>>>()
-                .map(|code| (code, n))
-        })
-        .collect()
-}
+    /// one of the shortest sequences of presses by hand to type the code
+    pub fn presses(&mut self, code: &str) -> Result<String> {
+        let len = self.min_presses(code)?;
+        if len > MAX_PRESSES {
+            bail!("{len} presses is too many to build");
+        }
+        let mut presses = Vec::with_capacity(len as usize);
+        let mut last = 'A';
+        for c in code.chars() {
+            let dir_seqs = self.code_pad.seqs_to_press(last, c)?;
+            let seq = self.min_seq(dir_seqs, self.depth);
+            self.expand(&seq, self.depth, &mut presses);
+            last = c;
+        }
+        Ok(presses.into_iter().collect())
+    }
 
-fn complexity(
-    mem: &mut HashMap<(Dir, Dir, u8), u64>,
-    code: &[Numeric],
-    code_num: u64,
-    dir_keypads: u8,
-) -> u64 {
-    let mut last_n = &Numeric::A;
-    let mut len = 0;
-    for n in code {
-        // first robot, numeric keypad
-        let dir_seqs = last_n.seqs_to_press(n);
+    /// push the presses by hand for `seq` pressed on a dir keypad `keypads` robots down
+    fn expand(&mut self, seq: &[char], keypads: usize, presses: &mut Vec<char>) {
+        if keypads == 0 {
+            presses.extend(seq);
+            return;
+        }
+        let mut last_d = 'A';
+        for d in seq {
+            let dir_seqs = self
+                .dir_pad
+                .seqs(self.dir_pad.keys[&last_d], self.dir_pad.keys[d])
+                .expect("checked in Robots::new");
+            let next = self.min_seq(dir_seqs, keypads - 1);
+            self.expand(&next, keypads - 1, presses);
+            last_d = *d;
+        }
+    }
 
-        // me and remaining robots, dir keypad
-        len += min_length_seq(mem, &dir_seqs, dir_keypads);
+    fn min_seq(&mut self, seqs: Vec<Vec<char>>, keypads: usize) -> Vec<char> {
+        seqs.into_iter()
+            .min_by_key(|s| {
+                min_length_seq(
+                    &self.dir_pad,
+                    &mut self.mem,
+                    std::slice::from_ref(s),
+                    keypads,
+                )
+            })
+            .unwrap_or_default()
+    }
 
-        last_n = n;
+    /// replay presses by hand through each keypad, returning what is pressed on each from the
+    /// first robot's dir keypad down to the code keypad
+    pub fn replay(&self, presses: &str) -> Result<Vec<String>> {
+        let mut layers = vec![];
+        let mut pressed = presses.chars().collect::<Vec<_>>();
+        for _ in 0..self.depth {
+            pressed = self.dir_pad.replay(&pressed)?;
+            layers.push(pressed.iter().collect());
+        }
+        layers.push(self.code_pad.replay(&pressed)?.into_iter().collect());
+        Ok(layers)
+    }
+
+    /// fewest presses times the numeric part of the code
+    pub fn complexity(&mut self, code: &str) -> Result<u64> {
+        let code_num = code
+            .chars()
+            .filter_map(|c| c.to_digit(10))
+            .fold(0, |n, d| n * 10 + d as u64);
+        Ok(self.min_presses(code)? * code_num)
     }
-    len * code_num
 }
 
-fn min_length_seq(mem: &mut HashMap<(Dir, Dir, u8), u64>, seqs: &[Vec<Dir>], keypads: u8) -> u64 {
+fn min_length_seq(
+    dir_pad: &Keypad,
+    mem: &mut Memo<(char, char, usize), u64>,
+    seqs: &[Vec<char>],
+    keypads: usize,
+) -> u64 {
     seqs.iter()
         .map(|s| {
             let mut len = 0;
-            let mut last_d = &Dir::A;
+            let mut last_d = 'A';
             for d in s {
-                len += min_length_press(mem, last_d, d, keypads);
-                last_d = d;
+                len += min_length_press(dir_pad, mem, last_d, *d, keypads);
+                last_d = *d;
             }
             len
         })
         .min()
-        .unwrap()
+        .expect("a key that can be reached has a sequence to press it")
 }
 
 fn min_length_press(
-    mem: &mut HashMap<(Dir, Dir, u8), u64>,
-    from: &Dir,
-    to: &Dir,
-    keypads_left: u8,
+    dir_pad: &Keypad,
+    mem: &mut Memo<(char, char, usize), u64>,
+    from: char,
+    to: char,
+    keypads_left: usize,
 ) -> u64 {
     if keypads_left == 0 {
         return 1;
     }
-    let k = (from.clone(), to.clone(), keypads_left);
-    if let Some(min) = mem.get(&k) {
-        return *min;
-    }
-    let seqs = from.seqs_to_press(to);
-    let min = min_length_seq(mem, &seqs, keypads_left - 1);
-    mem.insert(k, min);
-    min
+    mem.get_or_compute(&(from, to, keypads_left), |mem| {
+        let seqs = dir_pad
+            .seqs(dir_pad.keys[&from], dir_pad.keys[&to])
+            .expect("checked in Robots::new");
+        min_length_seq(dir_pad, mem, &seqs, keypads_left - 1)
+    })
+}
+
+/// sum of the code complexities with the given keypads and number of directional robots
+pub fn total_complexity(
+    input: &str,
+    code_pad: Keypad,
+    dir_pad: Keypad,
+    depth: usize,
+) -> Result<u64> {
+    let mut robots = Robots::new(code_pad, dir_pad, depth)?;
+    input.lines().map(|code| robots.complexity(code)).sum()
 }
 
 pub fn solve_one(input: &str) -> Result<String> {
-    let codes = parse_input(input)?;
-    let mut mem = HashMap::new();
-    Ok(codes
-        .into_iter()
-        .map(|(code, n)| complexity(&mut mem, &code, n, 2))
-        .sum::<u64>()
-        .to_string())
+    Ok(total_complexity(
+        input,
+        Keypad::new(NUMERIC_KEYPAD)?,
+        Keypad::new(DIR_KEYPAD)?,
+        2,
+    )?
+    .to_string())
 }
 
 pub fn solve_two(input: &str) -> Result<String> {
-    let codes = parse_input(input)?;
-    let mut mem = HashMap::new();
-    Ok(codes
-        .into_iter()
-        .map(|(code, n)| complexity(&mut mem, &code, n, 25))
-        .sum::<u64>()
-        .to_string())
+    Ok(total_complexity(
+        input,
+        Keypad::new(NUMERIC_KEYPAD)?,
+        Keypad::new(DIR_KEYPAD)?,
+        25,
+    )?
+    .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [(&str, u64); 5] = [
+        ("029A", 68),
+        ("980A", 60),
+        ("179A", 68),
+        ("456A", 64),
+        ("379A", 64),
+    ];
+
+    fn robots(code_pad: &str, depth: usize) -> Robots {
+        Robots::new(
+            Keypad::new(code_pad).unwrap(),
+            Keypad::new(DIR_KEYPAD).unwrap(),
+            depth,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn example_presses() {
+        let mut robots = robots(NUMERIC_KEYPAD, 2);
+        for (code, len) in EXAMPLE {
+            assert_eq!(robots.min_presses(code).unwrap(), len, "{code}");
+            let presses = robots.presses(code).unwrap();
+            assert_eq!(presses.len() as u64, len, "{code}");
+            assert_eq!(robots.replay(&presses).unwrap().last().unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn example_complexity() {
+        let input = EXAMPLE.map(|(code, _)| code).join("\n");
+        assert_eq!(solve_one(&input).unwrap(), "126384");
+    }
+
+    #[test]
+    fn path_around_gap() {
+        // the only route from A to 2 goes down, across and back up
+        let keypad = Keypad::new("A#2\n345").unwrap();
+        assert_eq!(
+            keypad.seqs_to_press('A', '2').unwrap(),
+            vec![vec!['v', '>', '>', '^', 'A']]
+        );
+        let mut robots = robots("A#2\n345", 2);
+        let presses = robots.presses("2").unwrap();
+        assert_eq!(robots.min_presses("2").unwrap(), presses.len() as u64);
+        assert_eq!(robots.replay(&presses).unwrap().last().unwrap(), "2");
+    }
+
+    #[test]
+    fn unreachable_key() {
+        let keypad = Keypad::new("A#2\n#45").unwrap();
+        assert!(keypad.seqs_to_press('A', '2').is_err());
+        let mut robots = robots("A#2\n#45", 2);
+        assert!(robots.min_presses("2").is_err());
+    }
 }
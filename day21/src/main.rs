@@ -1,6 +1,62 @@
+use std::fs;
+
+use anyhow::Result;
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day21, part1)]
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// number of robots using directional keypads, prints the total complexity instead of solving
+    /// as do the keypad options
+    #[arg(long)]
+    depth: Option<usize>,
+    /// file with the keypad grid the code is typed on, '#' for the gap
+    #[arg(long, value_name = "FILE")]
+    code_keypad: Option<String>,
+    /// file with the directional keypad grid, '#' for the gap
+    #[arg(long, value_name = "FILE")]
+    dir_keypad: Option<String>,
+    /// print a shortest press sequence for each code and what it types on each keypad
+    #[arg(long)]
+    show: bool,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        let custom = self.code_keypad.is_some() || self.dir_keypad.is_some();
+        if self.depth.is_none() && !self.show && !custom {
+            return Ok(false);
+        }
+        let depth = self.depth.unwrap_or(2);
+        let keypad = |file: &Option<String>, default: &str| match file {
+            Some(file) => day21::Keypad::new(&fs::read_to_string(file)?),
+            None => day21::Keypad::new(default),
+        };
+        let code_pad = keypad(&self.code_keypad, day21::NUMERIC_KEYPAD)?;
+        let dir_pad = keypad(&self.dir_keypad, day21::DIR_KEYPAD)?;
+        if !self.show {
+            println!(
+                "complexity:\n{}",
+                day21::total_complexity(input, code_pad, dir_pad, depth)?
+            );
+            return Ok(true);
+        }
+        let mut robots = day21::Robots::new(code_pad, dir_pad, depth)?;
+        for code in input.lines() {
+            let presses = robots.presses(code)?;
+            println!("{code}: {} presses\n{presses}", presses.len());
+            let layers = robots.replay(&presses)?;
+            for layer in &layers {
+                println!("{layer}");
+            }
+            if layers.last().map(String::as_str) != Some(code) {
+                println!("presses do not type {code}");
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[aoc(day21, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day21::solve_one(input)
 }
@@ -0,0 +1,82 @@
+//! compares the flat array banana count against the previous per-buyer hash map approach
+//!
+//! `cargo bench -p day22`, add `--features rayon` to time the parallel count
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::iter;
+use std::time::{Duration, Instant};
+
+const BUYERS: u32 = 2000;
+const RUNS: u32 = 5;
+
+fn next_secret(mut secret: i32) -> i32 {
+    const PRUNE_MASK: i32 = 2i32.pow(24) - 1;
+    secret ^= secret << 6;
+    secret &= PRUNE_MASK;
+    secret ^= secret >> 5;
+    secret &= PRUNE_MASK;
+    secret ^= secret << 11;
+    secret &= PRUNE_MASK;
+    secret
+}
+
+/// best total using a hash map of sequences per buyer
+fn hash_map_bananas(buyers: &[i32]) -> i32 {
+    buyers
+        .iter()
+        .map(|b| {
+            let prices = iter::successors(Some(*b), |s| Some(next_secret(*s)))
+                .take(2000)
+                .map(|s| s % 10)
+                .collect::<Vec<_>>();
+            let mut seqs = HashMap::new();
+            for w in prices.windows(5) {
+                let key = w
+                    .windows(2)
+                    .fold(0, |k, p| (k << 5) | (p[1] - p[0] + 10) as u32);
+                seqs.entry(key).or_insert(w[4]);
+            }
+            seqs
+        })
+        .fold(HashMap::new(), |mut acc, seqs| {
+            for (k, v) in seqs {
+                *acc.entry(k).or_insert(0) += v;
+            }
+            acc
+        })
+        .into_values()
+        .max()
+        .unwrap_or(0)
+}
+
+fn time<T: PartialEq + std::fmt::Debug>(name: &str, f: impl Fn() -> T) -> (Duration, T) {
+    let result = f();
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        black_box(f());
+    }
+    let per_run = start.elapsed() / RUNS;
+    println!("{name:>10}: {per_run:?}");
+    (per_run, result)
+}
+
+fn main() {
+    let buyers = (0..BUYERS)
+        .map(|i| next_secret(i as i32 * 7919 + 1))
+        .collect::<Vec<_>>();
+    let input = buyers
+        .iter()
+        .map(|b| b.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let (base, expected) = time("hash map", || hash_map_bananas(&buyers).to_string());
+    let (flat, found) = time("flat", || day22::solve_two(&input).unwrap());
+    assert_eq!(expected, found);
+    println!(
+        "{:>10}: {:.1}x",
+        "speedup",
+        base.as_secs_f64() / flat.as_secs_f64()
+    );
+}
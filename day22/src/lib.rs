@@ -1,9 +1,11 @@
-use std::{collections::HashMap, iter};
+use std::cmp::Reverse;
+use std::fmt::Write;
+use std::iter;
 
 use anyhow::Result;
 
-const PRUNE_MASK: i32 = 2i32.pow(24) - 1;
-fn next_secret(mut secret: i32) -> i32 {
+const PRUNE_MASK: u32 = 2u32.pow(24) - 1;
+fn next_secret(mut secret: u32) -> u32 {
     secret ^= secret << 6;
     secret &= PRUNE_MASK;
     secret ^= secret >> 5;
@@ -13,59 +15,252 @@ fn next_secret(mut secret: i32) -> i32 {
     secret
 }
 
+/// buyers stepped together, so [`next_secrets`] compiles to vector instructions
+const LANES: usize = 16;
+
+/// [`next_secret`] for a lane of buyers at once
+fn next_secrets(secrets: &mut [u32; LANES]) {
+    for secret in secrets.iter_mut() {
+        *secret = next_secret(*secret);
+    }
+}
+
+/// buyers split into full lanes, the last padded with zero secrets
+fn lanes(buyers: &[u32]) -> impl Iterator<Item = ([u32; LANES], usize)> + '_ {
+    buyers.chunks(LANES).map(|chunk| {
+        let mut secrets = [0; LANES];
+        secrets[..chunk.len()].copy_from_slice(chunk);
+        (secrets, chunk.len())
+    })
+}
+
+pub fn parse_input(input: &str) -> Result<Vec<u32>> {
+    input.lines().map(|v| Ok(v.parse::<u32>()?)).collect()
+}
+
 pub fn solve_one(input: &str) -> Result<String> {
-    let monkeys = input
-        .lines()
-        .map(|v| Ok(v.parse::<i32>()?))
-        .collect::<Result<Vec<_>>>()?;
-
-    Ok(monkeys
-        .into_iter()
-        .map(|s| {
-            iter::successors(Some(s), |s| Some(next_secret(*s)))
-                .nth(2000)
-                .unwrap() as u64
+    let buyers = parse_input(input)?;
+
+    Ok(lanes(&buyers)
+        .map(|(mut secrets, len)| {
+            for _ in 0..2000 {
+                next_secrets(&mut secrets);
+            }
+            secrets[..len].iter().map(|s| *s as u64).sum::<u64>()
         })
         .sum::<u64>()
         .to_string())
 }
 
-pub fn solve_two(input: &str) -> Result<String> {
-    let monkeys = input
-        .lines()
-        .map(|v| Ok(v.parse::<i32>()?))
-        .collect::<Result<Vec<_>>>()?;
-
-    Ok(monkeys
-        .clone()
-        .into_iter()
-        .flat_map(|s| {
-            iter::successors(Some(s), |s| Some(next_secret(*s)))
-                .map(|s| s % 10)
-                .take(2000)
-                .collect::<Vec<_>>()
-                .windows(2)
-                .map(|w| (w[1] - w[0], w[1]))
-                .collect::<Vec<_>>()
-                .windows(4)
-                .map(|w| {
-                    let mut k = (w[0].0 + 10) << 5;
-                    k |= w[1].0 + 10;
-                    k <<= 5;
-                    k |= w[2].0 + 10;
-                    k <<= 5;
-                    k |= w[3].0 + 10;
-                    (k, w[3].1)
-                })
-                .rev()
-                .collect::<HashMap<_, _>>()
+/// prices seen per buyer, including the initial secret
+const PRICES: usize = 2000;
+/// each change is in `-9..=9`
+const CHANGES: usize = 19;
+/// every sequence of four changes, packed base 19
+pub const SEQS: usize = CHANGES.pow(4);
+
+/// add the price at the first occurrence of each change sequence for each buyer to `totals`
+///
+/// `seen` holds the stamp of the last buyer to see each sequence, buyers are stamped from
+/// `stamp` upwards
+fn add_bananas(buyers: &[u32], totals: &mut [u32], seen: &mut [u32], mut stamp: u32) {
+    let mut prices = vec![[0; LANES]; PRICES];
+    for (mut secrets, len) in lanes(buyers) {
+        prices[0] = secrets.map(|s| s % 10);
+        for lane_prices in prices.iter_mut().skip(1) {
+            next_secrets(&mut secrets);
+            *lane_prices = secrets.map(|s| s % 10);
+        }
+        // buyers are scanned one at a time so a stamp is only ever compared with its own
+        for lane in 0..len {
+            let mut seq = 0;
+            for (i, w) in prices.windows(2).enumerate() {
+                let price = w[1][lane];
+                seq = (seq * CHANGES + (price + 9 - w[0][lane]) as usize) % SEQS;
+                if i >= 3 && seen[seq] != stamp {
+                    seen[seq] = stamp;
+                    totals[seq] += price;
+                }
+            }
+            stamp += 1;
+        }
+    }
+}
+
+/// total bananas for each packed change sequence
+#[cfg(not(feature = "rayon"))]
+pub fn bananas(buyers: &[u32]) -> Vec<u32> {
+    let mut totals = vec![0; SEQS];
+    add_bananas(buyers, &mut totals, &mut vec![0; SEQS], 1);
+    totals
+}
+
+/// total bananas for each packed change sequence
+#[cfg(feature = "rayon")]
+pub fn bananas(buyers: &[u32]) -> Vec<u32> {
+    use rayon::prelude::*;
+
+    buyers
+        .par_chunks(LANES * 16)
+        .fold(
+            || (vec![0; SEQS], vec![0; SEQS], 1),
+            |(mut totals, mut seen, stamp), chunk| {
+                add_bananas(chunk, &mut totals, &mut seen, stamp);
+                (totals, seen, stamp + chunk.len() as u32)
+            },
+        )
+        .map(|(totals, _, _)| totals)
+        .reduce(
+            || vec![0; SEQS],
+            |mut acc, totals| {
+                acc.iter_mut().zip(totals).for_each(|(a, t)| *a += t);
+                acc
+            },
+        )
+}
+
+/// the four price changes in a packed sequence
+pub fn unpack(seq: usize) -> [i32; 4] {
+    let mut changes = [0; 4];
+    let mut seq = seq;
+    for change in changes.iter_mut().rev() {
+        *change = (seq % CHANGES) as i32 - 9;
+        seq /= CHANGES;
+    }
+    changes
+}
+
+/// prices a buyer offers, starting with the initial secret
+fn prices(secret: u32) -> impl Iterator<Item = u32> {
+    iter::successors(Some(secret), |s| Some(next_secret(*s)))
+        .map(|s| s % 10)
+        .take(PRICES)
+}
+
+/// where a buyer first sees a change sequence, `index` counts the secrets generated before
+/// the sale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sale {
+    pub index: usize,
+    pub price: u32,
+}
+
+/// the sale for a packed change sequence, if the buyer ever sees it
+pub fn sale(secret: u32, seq: usize) -> Option<Sale> {
+    let prices = prices(secret).collect::<Vec<_>>();
+    let mut seen = 0;
+    prices.windows(2).enumerate().find_map(|(i, w)| {
+        seen = (seen * CHANGES + (w[1] + 9 - w[0]) as usize) % SEQS;
+        (i >= 3 && seen == seq).then_some(Sale {
+            index: i + 1,
+            price: w[1],
         })
-        .fold(HashMap::new(), |mut acc, (k, v)| {
-            acc.entry(k).and_modify(|s| *s += v).or_insert(v);
-            acc
+    })
+}
+
+/// change sequence with the bananas it buys across all buyers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ranked {
+    pub changes: [i32; 4],
+    pub bananas: u32,
+}
+
+/// the `n` sequences buying the most bananas, best first, ties broken by packed sequence
+pub fn ranking(totals: &[u32], n: usize) -> Vec<Ranked> {
+    let mut seqs = (0..totals.len()).collect::<Vec<_>>();
+    seqs.sort_by_key(|seq| (Reverse(totals[*seq]), *seq));
+    seqs.into_iter()
+        .take(n)
+        .map(|seq| Ranked {
+            changes: unpack(seq),
+            bananas: totals[seq],
         })
-        .values()
-        .max()
-        .unwrap()
-        .to_string())
+        .collect()
+}
+
+/// best sequence with the sale made by each buyer and the top sequences
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub best: Ranked,
+    /// initial secret of each buyer with their sale
+    pub sales: Vec<(u32, Option<Sale>)>,
+    pub top: Vec<Ranked>,
+}
+impl Report {
+    pub fn new(buyers: &[u32], top: usize) -> Self {
+        let totals = bananas(buyers);
+        let mut ranked = ranking(&totals, top.max(1));
+        let best = ranked[0];
+        ranked.truncate(top);
+        let seq = best
+            .changes
+            .iter()
+            .fold(0, |seq, c| seq * CHANGES + (c + 9) as usize);
+        Self {
+            best,
+            sales: buyers.iter().map(|b| (*b, sale(*b, seq))).collect(),
+            top: ranked,
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let changes = |c: &[i32; 4]| c.map(|c| c.to_string()).join(",");
+        let mut out = format!(
+            "best: {} buys {} bananas\n",
+            changes(&self.best.changes),
+            self.best.bananas
+        );
+        for (buyer, sale) in &self.sales {
+            match sale {
+                Some(Sale { index, price }) => {
+                    writeln!(out, "buyer {buyer}: sells for {price} at secret {index}")
+                }
+                None => writeln!(out, "buyer {buyer}: never sells"),
+            }
+            .unwrap();
+        }
+        writeln!(out, "top {}:", self.top.len()).unwrap();
+        for (i, r) in self.top.iter().enumerate() {
+            writeln!(
+                out,
+                "{:>3}. {:<12} {}",
+                i + 1,
+                changes(&r.changes),
+                r.bananas
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let ranked = |r: &Ranked| {
+            format!(
+                r#"{{"changes":[{}],"bananas":{}}}"#,
+                r.changes.map(|c| c.to_string()).join(","),
+                r.bananas
+            )
+        };
+        let sales = self
+            .sales
+            .iter()
+            .map(|(buyer, sale)| match sale {
+                Some(Sale { index, price }) => {
+                    format!(r#"{{"buyer":{buyer},"price":{price},"index":{index}}}"#)
+                }
+                None => format!(r#"{{"buyer":{buyer},"price":null,"index":null}}"#),
+            })
+            .collect::<Vec<_>>();
+        format!(
+            r#"{{"best":{},"sales":[{}],"top":[{}]}}"#,
+            ranked(&self.best),
+            sales.join(","),
+            self.top.iter().map(ranked).collect::<Vec<_>>().join(",")
+        )
+    }
+}
+
+pub fn solve_two(input: &str) -> Result<String> {
+    let buyers = parse_input(input)?;
+    Ok(bananas(&buyers).into_iter().max().unwrap_or(0).to_string())
 }
@@ -1,6 +1,41 @@
+use anyhow::Result;
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day22, part1)]
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// report the best change sequence, each buyer's sale and the top sequences instead of
+    /// solving
+    #[arg(long)]
+    report: bool,
+    /// number of sequences to rank in the report
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    top: usize,
+    /// report format
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        if !self.report {
+            return Ok(false);
+        }
+        let report = day22::Report::new(&day22::parse_input(input)?, self.top);
+        match self.format {
+            Format::Text => print!("{}", report.to_text()),
+            Format::Json => println!("{}", report.to_json()),
+        }
+        Ok(true)
+    }
+}
+
+#[aoc(day22, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day22::solve_one(input)
 }
@@ -1,36 +1,24 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::fmt;
 
 use anyhow::{anyhow, bail, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-enum GateType {
+pub enum GateType {
     And,
     Xor,
     Or,
 }
 #[derive(Clone, Debug, PartialEq, Eq)]
-struct Gate {
-    gate_type: GateType,
-    in1: String,
-    in2: String,
-    out: String,
+pub struct Gate {
+    pub gate_type: GateType,
+    pub in1: String,
+    pub in2: String,
+    pub out: String,
 }
 impl Gate {
-    fn resolve(&self, wires: &HashMap<String, bool>) -> Option<(String, bool)> {
-        wires
-            .get(&self.in1)
-            .zip(wires.get(&self.in2))
-            .map(|(in1, in2)| {
-                (
-                    self.out.clone(),
-                    match self.gate_type {
-                        GateType::And => in1 & in2,
-                        GateType::Xor => in1 ^ in2,
-                        GateType::Or => in1 | in2,
-                    },
-                )
-            })
-    }
     fn swap_outs(&mut self, other: &mut Self) {
         std::mem::swap(&mut self.out, &mut other.out);
     }
@@ -45,7 +33,7 @@ impl Gate {
     }
 }
 
-fn parse_input(input: &str) -> Result<(HashMap<String, bool>, Vec<Gate>)> {
+pub fn parse_input(input: &str) -> Result<(HashMap<String, bool>, Vec<Gate>)> {
     let (wires, gates) = input
         .split_once("\n\n")
         .ok_or_else(|| anyhow!("missing empty line"))?;
@@ -92,69 +80,684 @@ fn parse_input(input: &str) -> Result<(HashMap<String, bool>, Vec<Gate>)> {
     ))
 }
 
-fn get_z(mut wires: HashMap<String, bool>, gates: &[Gate]) -> Option<u64> {
-    let mut z: u64 = 0;
-    let mut gates = gates.iter().collect::<Vec<&Gate>>();
-    let mut wires_count = wires.len();
-    while !gates.is_empty() {
-        gates.retain(|gate| {
-            if let Some((wire, res)) = gate.resolve(&wires) {
-                if res && wire.starts_with('z') {
-                    let z_i = wire[1..].parse::<usize>().unwrap();
-                    z |= 1 << z_i;
+/// gates compiled to wire indices in topological order, evaluated 64 inputs at a time with
+/// each bit of a wire value being one input
+#[derive(Clone, Debug)]
+pub struct Circuit {
+    wires: Vec<String>,
+    /// wires not output by a gate
+    inputs: Vec<usize>,
+    /// `(gate type, in1, in2, out)` in evaluation order
+    ops: Vec<(GateType, usize, usize, usize)>,
+    /// wire of each x, y and z bit
+    x: Vec<Option<usize>>,
+    y: Vec<Option<usize>>,
+    z: Vec<Option<usize>>,
+}
+impl Circuit {
+    /// intern the wires and sort the gates, erroring if they form a cycle or an x, y or z bus
+    /// is wider than 64 bits
+    pub fn compile(gates: &[Gate]) -> Result<Self> {
+        let mut wires = vec![];
+        let mut index = HashMap::<String, usize>::new();
+        let mut intern = |wire: &str| match index.get(wire) {
+            Some(i) => *i,
+            None => {
+                wires.push(wire.to_string());
+                index.insert(wire.to_string(), wires.len() - 1);
+                wires.len() - 1
+            }
+        };
+        let gates = gates
+            .iter()
+            .map(|gate| {
+                (
+                    gate.gate_type.clone(),
+                    intern(&gate.in1),
+                    intern(&gate.in2),
+                    intern(&gate.out),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut driver = vec![None; wires.len()];
+        for (i, (_, _, _, out)) in gates.iter().enumerate() {
+            if driver[*out].replace(i).is_some() {
+                bail!("wire {} is output by more than one gate", wires[*out]);
+            }
+        }
+        // kahn's algorithm over the gates
+        let mut readers = vec![vec![]; wires.len()];
+        let mut waiting = vec![0; gates.len()];
+        for (i, (_, in1, in2, _)) in gates.iter().enumerate() {
+            for wire in [in1, in2] {
+                if driver[*wire].is_some() {
+                    readers[*wire].push(i);
+                    waiting[i] += 1;
                 }
-                wires.insert(wire, res);
-                false
-            } else {
-                true
             }
-        });
-        let next_count = wires.len();
-        if wires_count == next_count {
-            return None;
         }
-        wires_count = next_count;
+        let mut ready = (0..gates.len())
+            .filter(|i| waiting[*i] == 0)
+            .collect::<Vec<_>>();
+        let mut ops = Vec::with_capacity(gates.len());
+        while let Some(i) = ready.pop() {
+            ops.push(gates[i].clone());
+            for reader in &readers[gates[i].3] {
+                waiting[*reader] -= 1;
+                if waiting[*reader] == 0 {
+                    ready.push(*reader);
+                }
+            }
+        }
+        if ops.len() != gates.len() {
+            let mut unresolved = gates
+                .iter()
+                .zip(waiting)
+                .filter(|(_, waiting)| *waiting > 0)
+                .map(|((_, _, _, out), _)| wires[*out].as_str())
+                .collect::<Vec<_>>();
+            unresolved.sort();
+            bail!(
+                "gates form a cycle, unresolved wires {}",
+                unresolved.join(",")
+            );
+        }
+
+        let bus = |prefix: char| {
+            let mut bus = vec![];
+            for (i, wire) in wires.iter().enumerate() {
+                if let Some(bit) = wire
+                    .strip_prefix(prefix)
+                    .and_then(|bit| bit.parse::<usize>().ok())
+                {
+                    if bit >= 64 {
+                        bail!("wire {wire} is past the 64 bits a bus can hold");
+                    }
+                    if bus.len() <= bit {
+                        bus.resize(bit + 1, None);
+                    }
+                    bus[bit] = Some(i);
+                }
+            }
+            Ok(bus)
+        };
+        Ok(Self {
+            inputs: (0..wires.len()).filter(|w| driver[*w].is_none()).collect(),
+            ops,
+            x: bus('x')?,
+            y: bus('y')?,
+            z: bus('z')?,
+            wires,
+        })
+    }
+
+    fn run(&self, values: &mut [u64]) {
+        for (gate_type, in1, in2, out) in &self.ops {
+            values[*out] = match gate_type {
+                GateType::And => values[*in1] & values[*in2],
+                GateType::Xor => values[*in1] ^ values[*in2],
+                GateType::Or => values[*in1] | values[*in2],
+            };
+        }
+    }
+
+    /// z for a single set of input wire values
+    pub fn eval(&self, wires: &HashMap<String, bool>) -> Result<u64> {
+        let mut values = vec![0; self.wires.len()];
+        for i in &self.inputs {
+            let wire = &self.wires[*i];
+            values[*i] = *wires
+                .get(wire)
+                .ok_or_else(|| anyhow!("no value for input wire {wire}"))?
+                as u64;
+        }
+        self.run(&mut values);
+        Ok(self
+            .z
+            .iter()
+            .enumerate()
+            .filter_map(|(bit, wire)| wire.map(|wire| (values[wire] & 1) << bit))
+            .sum())
+    }
+
+    /// z for up to 64 pairs of x and y at once, missing input bits are 0
+    pub fn add(&self, xys: &[(u64, u64)]) -> Vec<u64> {
+        assert!(xys.len() <= 64, "at most 64 inputs at once");
+        let mut values = vec![0; self.wires.len()];
+        let lanes = |bus: &[Option<usize>], values: &mut [u64], get: fn(&(u64, u64)) -> u64| {
+            for (bit, wire) in bus.iter().enumerate() {
+                if let Some(wire) = wire {
+                    values[*wire] = xys
+                        .iter()
+                        .enumerate()
+                        .map(|(lane, xy)| (get(xy) >> bit & 1) << lane)
+                        .sum();
+                }
+            }
+        };
+        lanes(&self.x, &mut values, |(x, _)| *x);
+        lanes(&self.y, &mut values, |(_, y)| *y);
+        self.run(&mut values);
+        (0..xys.len())
+            .map(|lane| {
+                self.z
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(bit, wire)| wire.map(|wire| (values[wire] >> lane & 1) << bit))
+                    .sum()
+            })
+            .collect()
     }
-    Some(z)
 }
 
 pub fn solve_one(input: &str) -> Result<String> {
     let (wires, gates) = parse_input(input)?;
-    Ok(get_z(wires, &gates).unwrap().to_string())
+    Ok(Circuit::compile(&gates)?.eval(&wires)?.to_string())
 }
 
-pub fn solve_two(input: &str) -> Result<String> {
-    let (wires, mut gates) = parse_input(input)?;
-    let gates = &mut gates;
-    let x_y_len = wires
+/// number of x and y input bits
+pub fn input_bits(wires: &HashMap<String, bool>) -> Result<usize> {
+    wires
         .keys()
         .map(|wire| {
-            wire.trim_start_matches("x")
-                .trim_start_matches("y")
+            wire.strip_prefix('x')
+                .or_else(|| wire.strip_prefix('y'))
+                .ok_or_else(|| anyhow!("unexpected input wire {wire}"))?
                 .parse::<usize>()
-                .unwrap()
-                + 1
+                .map(|i| i + 1)
+                .map_err(|e| anyhow!("unexpected input wire {wire} - {e}"))
         })
-        .max()
-        .unwrap();
+        .try_fold(0, |max, i| Ok(max.max(i?)))
+}
+
+/// gate expected in a bit of a ripple carry adder
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdderGate {
+    /// `x XOR y`, the sum for bit 0
+    InputXor,
+    /// `x AND y`, the carry for bit 0
+    InputAnd,
+    /// `(x XOR y) XOR carry`
+    Sum,
+    /// `(x XOR y) AND carry`
+    CarryAnd,
+    /// `(x AND y) OR ((x XOR y) AND carry)`
+    Carry,
+}
+impl AdderGate {
+    fn gate_type(&self) -> GateType {
+        match self {
+            Self::InputXor | Self::Sum => GateType::Xor,
+            Self::InputAnd | Self::CarryAnd => GateType::And,
+            Self::Carry => GateType::Or,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdderFault {
+    /// no gate with these inputs
+    Missing {
+        bit: usize,
+        gate: AdderGate,
+        in1: String,
+        in2: String,
+    },
+    /// no gate outputs the wire
+    NoDriver { bit: usize, wire: String },
+    /// gate outputs `out` but should output `expected`
+    Miswired {
+        bit: usize,
+        gate: AdderGate,
+        out: String,
+        expected: String,
+    },
+}
+impl fmt::Display for AdderFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing {
+                bit,
+                gate,
+                in1,
+                in2,
+            } => write!(
+                f,
+                "bit {bit}: missing {gate:?} gate {in1} {:?} {in2}",
+                gate.gate_type()
+            ),
+            Self::NoDriver { bit, wire } => write!(f, "bit {bit}: no gate outputs {wire}"),
+            Self::Miswired {
+                bit,
+                gate,
+                out,
+                expected,
+            } => write!(
+                f,
+                "bit {bit}: {gate:?} gate outputs {out}, expected {expected}"
+            ),
+        }
+    }
+}
+
+/// check the gates form a ripple carry adder of `bits` input bits, rewiring gates as faults
+/// are found so later bits can still be checked
+///
+/// stops at the first [`AdderFault::Missing`] or [`AdderFault::NoDriver`] as the rest of the
+/// adder can not be followed
+pub fn verify_adder(gates: &[Gate], bits: usize) -> Vec<AdderFault> {
+    let mut verifier = Verifier {
+        gates: gates.to_vec(),
+        faults: vec![],
+    };
+    let _ = verifier.verify(bits);
+    verifier.faults
+}
+
+struct Verifier {
+    gates: Vec<Gate>,
+    faults: Vec<AdderFault>,
+}
+impl Verifier {
+    fn verify(&mut self, bits: usize) -> Option<()> {
+        let xor_i = self.expect(0, AdderGate::InputXor, "x00", "y00")?;
+        self.rewire(0, AdderGate::InputXor, xor_i, "z00")?;
+        let mut carry_i = self.expect(0, AdderGate::InputAnd, "x00", "y00")?;
+        // the carry into bit 1 is the input AND of bit 0
+        let mut carry_gate = AdderGate::InputAnd;
+
+        for bit in 1..bits {
+            let (x, y) = (format!("x{bit:02}"), format!("y{bit:02}"));
+            let xor_i = self.expect(bit, AdderGate::InputXor, &x, &y)?;
+            let and_i = self.expect(bit, AdderGate::InputAnd, &x, &y)?;
+
+            let xor_out = self.gates[xor_i].out.clone();
+            let carry = self.gates[carry_i].out.clone();
+            let sum_i = match find_gate_from_two_in(&self.gates, &xor_out, &carry, GateType::Xor) {
+                Some(sum_i) => sum_i,
+                None => {
+                    // one input to the sum is wrong, find it from the other
+                    if let Some(sum_i) = find_gate_from_one_in(&self.gates, &carry, GateType::Xor) {
+                        let other = self.gates[sum_i].match_in(&carry)?.to_string();
+                        self.rewire(bit, AdderGate::InputXor, xor_i, &other)?;
+                        sum_i
+                    } else if let Some(sum_i) =
+                        find_gate_from_one_in(&self.gates, &xor_out, GateType::Xor)
+                    {
+                        let other = self.gates[sum_i].match_in(&xor_out)?.to_string();
+                        self.rewire(bit, carry_gate, carry_i, &other)?;
+                        sum_i
+                    } else {
+                        return self.missing(bit, AdderGate::Sum, &xor_out, &carry);
+                    }
+                }
+            };
+            self.rewire(bit, AdderGate::Sum, sum_i, &format!("z{bit:02}"))?;
+
+            let xor_out = self.gates[xor_i].out.clone();
+            let carry = self.gates[carry_i].out.clone();
+            let carry_and_i = self.expect(bit, AdderGate::CarryAnd, &xor_out, &carry)?;
+
+            let and_out = self.gates[and_i].out.clone();
+            let carry_and_out = self.gates[carry_and_i].out.clone();
+            carry_i =
+                match find_gate_from_two_in(&self.gates, &and_out, &carry_and_out, GateType::Or) {
+                    Some(or_i) => or_i,
+                    None => {
+                        let ors = (
+                            find_gate_from_one_in(&self.gates, &and_out, GateType::Or),
+                            find_gate_from_one_in(&self.gates, &carry_and_out, GateType::Or),
+                        );
+                        // both are read by an OR when swapped with an AND of another bit, the
+                        // OR of the later bit reads a gate fed by the carry out of this one
+                        let ors = match ors {
+                            (Some(or_i), Some(later_i)) if self.feeds(or_i, later_i) => {
+                                (Some(or_i), None)
+                            }
+                            (Some(later_i), Some(or_i)) if self.feeds(or_i, later_i) => {
+                                (None, Some(or_i))
+                            }
+                            ors => ors,
+                        };
+                        match ors {
+                            (None, Some(or_i)) => {
+                                let other = self.gates[or_i].match_in(&carry_and_out)?.to_string();
+                                self.rewire(bit, AdderGate::InputAnd, and_i, &other)?;
+                                or_i
+                            }
+                            (Some(or_i), None) => {
+                                let other = self.gates[or_i].match_in(&and_out)?.to_string();
+                                self.rewire(bit, AdderGate::CarryAnd, carry_and_i, &other)?;
+                                or_i
+                            }
+                            _ => {
+                                return self.missing(
+                                    bit,
+                                    AdderGate::Carry,
+                                    &and_out,
+                                    &carry_and_out,
+                                )
+                            }
+                        }
+                    }
+                };
+            carry_gate = AdderGate::Carry;
+        }
+        self.rewire(bits, carry_gate, carry_i, &format!("z{bits:02}"))
+    }
+
+    /// whether an input of gate `later_i` is driven by a gate reading the output of gate `i`
+    fn feeds(&self, i: usize, later_i: usize) -> bool {
+        let later = &self.gates[later_i];
+        [&later.in1, &later.in2].into_iter().any(|wire| {
+            find_gate_from_out(&self.gates, wire)
+                .is_some_and(|driver_i| self.gates[driver_i].match_in(&self.gates[i].out).is_some())
+        })
+    }
+
+    fn expect(&mut self, bit: usize, gate: AdderGate, in1: &str, in2: &str) -> Option<usize> {
+        match find_gate_from_two_in(&self.gates, in1, in2, gate.gate_type()) {
+            Some(i) => Some(i),
+            None => self.missing(bit, gate, in1, in2),
+        }
+    }
+
+    fn missing<T>(&mut self, bit: usize, gate: AdderGate, in1: &str, in2: &str) -> Option<T> {
+        self.faults.push(AdderFault::Missing {
+            bit,
+            gate,
+            in1: in1.to_string(),
+            in2: in2.to_string(),
+        });
+        None
+    }
+
+    /// make gate `i` output `expected`, swapping with the gate currently outputting it
+    fn rewire(&mut self, bit: usize, gate: AdderGate, i: usize, expected: &str) -> Option<()> {
+        if self.gates[i].out == expected {
+            return Some(());
+        }
+        let Some(other_i) = find_gate_from_out(&self.gates, expected) else {
+            self.faults.push(AdderFault::NoDriver {
+                bit,
+                wire: expected.to_string(),
+            });
+            return None;
+        };
+        self.faults.push(AdderFault::Miswired {
+            bit,
+            gate,
+            out: self.gates[i].out.clone(),
+            expected: expected.to_string(),
+        });
+        swap_gates(&mut self.gates, i, other_i);
+        Some(())
+    }
+}
+
+/// gate output wires swapped to fix the [`AdderFault::Miswired`] faults, sorted
+pub fn fault_swaps(faults: &[AdderFault]) -> Vec<String> {
+    let mut swaps = faults
+        .iter()
+        .flat_map(|fault| match fault {
+            AdderFault::Miswired { out, expected, .. } => vec![out.clone(), expected.clone()],
+            _ => vec![],
+        })
+        .collect::<Vec<_>>();
+    swaps.sort();
+    swaps.dedup();
+    swaps
+}
+
+pub fn solve_two(input: &str) -> Result<String> {
+    let (wires, gates) = parse_input(input)?;
+    let faults = verify_adder(&gates, input_bits(&wires)?);
+    if faults
+        .iter()
+        .any(|fault| !matches!(fault, AdderFault::Miswired { .. }))
+    {
+        bail!(
+            "gates are not a ripple carry adder:\n{}",
+            faults
+                .iter()
+                .map(|fault| fault.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    Ok(fault_swaps(&faults).join(","))
+}
+
+/// random input where the circuit does not output `x + y`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AddFailure {
+    pub x: u64,
+    pub y: u64,
+    pub z: u64,
+    /// lowest bit of z that differs from `x + y`
+    pub bit: usize,
+}
+
+/// drive the gates with `trials` random inputs of `bits` bits and compare z to `x + y`,
+/// returning the failure with the lowest failing bit
+///
+/// errors if the gates do not compile or the sum of `bits` bit inputs does not fit in 64 bits
+pub fn check_random(
+    gates: &[Gate],
+    bits: usize,
+    trials: usize,
+    seed: u64,
+) -> Result<Option<AddFailure>> {
+    if bits >= 64 {
+        bail!("{bits} input bits do not fit a 64 bit sum");
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mask = (1u64 << bits) - 1;
+    let circuit = Circuit::compile(gates)?;
+    let mut lowest: Option<AddFailure> = None;
+    let mut remaining = trials;
+    while remaining > 0 {
+        let xys = (0..remaining.min(64))
+            .map(|_| (rng.gen::<u64>() & mask, rng.gen::<u64>() & mask))
+            .collect::<Vec<_>>();
+        remaining -= xys.len();
+        for (&(x, y), z) in xys.iter().zip(circuit.add(&xys)) {
+            if z == x + y {
+                continue;
+            }
+            let bit = (z ^ (x + y)).trailing_zeros() as usize;
+            if lowest.as_ref().is_none_or(|lowest| bit < lowest.bit) {
+                lowest = Some(AddFailure { x, y, z, bit });
+            }
+        }
+    }
+    Ok(lowest)
+}
+
+/// gates with the outputs of each pair of wires swapped
+pub fn swap_outputs(gates: &[Gate], pairs: &[(String, String)]) -> Result<Vec<Gate>> {
+    let mut gates = gates.to_vec();
+    for (out1, out2) in pairs {
+        let i1 =
+            find_gate_from_out(&gates, out1).ok_or_else(|| anyhow!("no gate outputs {out1}"))?;
+        let i2 =
+            find_gate_from_out(&gates, out2).ok_or_else(|| anyhow!("no gate outputs {out2}"))?;
+        swap_gates(&mut gates, i1, i2);
+    }
+    Ok(gates)
+}
+
+/// find a pairing of the `wires` whose output swaps pass [`check_random`]
+pub fn validate_swaps(
+    gates: &[Gate],
+    bits: usize,
+    wires: &[String],
+    trials: usize,
+    seed: u64,
+) -> Result<Option<Vec<(String, String)>>> {
+    if !wires.len().is_multiple_of(2) {
+        bail!("odd number of wires to swap: {}", wires.len());
+    }
+    for pairs in pairings(wires) {
+        let swapped = swap_outputs(gates, &pairs)?;
+        // a pairing that makes the gates cyclic is not the fix, others may still be
+        if Circuit::compile(&swapped).is_err() {
+            continue;
+        }
+        if check_random(&swapped, bits, trials, seed)?.is_none() {
+            return Ok(Some(pairs));
+        }
+    }
+    Ok(None)
+}
 
-    let mut swaps = HashSet::<usize>::new();
-    let mut used_gates = HashSet::new();
-    let x_y_0_xor_i = find_gate_from_two_in(gates, "x00", "y00", GateType::Xor).unwrap();
-    assert!(gates[x_y_0_xor_i].out == "z00");
+/// every way of splitting the wires into pairs
+fn pairings(wires: &[String]) -> Vec<Vec<(String, String)>> {
+    let Some((first, rest)) = wires.split_first() else {
+        return vec![vec![]];
+    };
+    (0..rest.len())
+        .flat_map(|i| {
+            let mut others = rest.to_vec();
+            let second = others.remove(i);
+            pairings(&others).into_iter().map(move |mut pairs| {
+                pairs.insert(0, (first.clone(), second.clone()));
+                pairs
+            })
+        })
+        .collect()
+}
+
+/// bit index of an `x`, `y` or `z` wire
+fn wire_bit(wire: &str) -> Option<usize> {
+    wire.strip_prefix(['x', 'y', 'z'])?.parse().ok()
+}
 
-    let carry_i = find_gate_from_two_in(gates, "x00", "y00", GateType::And).unwrap();
-    used_gates.insert(x_y_0_xor_i);
-    used_gates.insert(carry_i);
+/// bit index of each gate, the highest bit of any of its inputs
+fn gate_bits(gates: &[Gate]) -> Vec<usize> {
+    let mut wire_bits = HashMap::<&str, usize>::new();
+    let mut bits = vec![None; gates.len()];
+    // gates in a cycle never resolve, limit the passes
+    for _ in 0..gates.len() {
+        let mut changed = false;
+        for (gate, bit) in gates.iter().zip(bits.iter_mut()) {
+            if bit.is_some() {
+                continue;
+            }
+            let in_bit = |wire: &str| wire_bit(wire).or_else(|| wire_bits.get(wire).copied());
+            if let Some((in1, in2)) = in_bit(&gate.in1).zip(in_bit(&gate.in2)) {
+                *bit = Some(in1.max(in2));
+                wire_bits.insert(&gate.out, in1.max(in2));
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    bits.into_iter().map(|bit| bit.unwrap_or(0)).collect()
+}
 
-    build_adder(x_y_len, gates, &mut used_gates, &mut swaps, carry_i, 1);
+/// graphviz dot graph of the gates, grouped by bit with the `swapped` outputs highlighted
+pub fn to_dot(gates: &[Gate], swapped: &[String]) -> String {
+    let mut by_bit = HashMap::<usize, Vec<usize>>::new();
+    for (i, bit) in gate_bits(gates).into_iter().enumerate() {
+        by_bit.entry(bit).or_default().push(i);
+    }
+    let mut bits = by_bit.keys().copied().collect::<Vec<_>>();
+    bits.sort();
 
-    let mut swaps_out = swaps
-        .into_iter()
-        .map(|i| gates[i].out.clone())
+    let mut dot = String::from("digraph adder {\n    rankdir=LR;\n");
+    for bit in bits {
+        dot += &format!("    subgraph cluster_{bit} {{\n        label=\"bit {bit}\";\n");
+        for i in &by_bit[&bit] {
+            let gate = &gates[*i];
+            dot += &format!(
+                "        g{i} [label=\"{:?}\", shape=box{}];\n",
+                gate.gate_type,
+                if swapped.contains(&gate.out) {
+                    ", color=red, style=bold"
+                } else {
+                    ""
+                }
+            );
+        }
+        dot += "    }\n";
+    }
+    let drivers = gates
+        .iter()
+        .enumerate()
+        .map(|(i, gate)| (gate.out.as_str(), i))
+        .collect::<HashMap<_, _>>();
+    for (i, gate) in gates.iter().enumerate() {
+        for wire in [&gate.in1, &gate.in2] {
+            match drivers.get(wire.as_str()) {
+                Some(from) => dot += &format!("    g{from} -> g{i} [label=\"{wire}\"];\n"),
+                None => dot += &format!("    {wire} -> g{i};\n"),
+            }
+        }
+        if gate.out.starts_with('z') {
+            dot += &format!(
+                "    g{i} -> {}{};\n",
+                gate.out,
+                if swapped.contains(&gate.out) {
+                    " [color=red]"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+    dot += "}\n";
+    dot
+}
+
+/// structural verilog module `adder` of the gates, with `x`, `y` and `z` as buses
+///
+/// other wires are written as escaped identifiers, so names such as `and` are still valid
+pub fn to_verilog(gates: &[Gate], bits: usize) -> String {
+    let net = |wire: &str| match (wire.chars().next(), wire_bit(wire)) {
+        (Some(bus), Some(bit)) => format!("{bus}[{bit}]"),
+        _ => format!("\\{wire} "),
+    };
+    let z_bits = gates
+        .iter()
+        .filter(|gate| gate.out.starts_with('z'))
+        .filter_map(|gate| wire_bit(&gate.out))
+        .max()
+        .map_or(0, |bit| bit + 1);
+
+    let mut verilog = format!(
+        "module adder(\n    input [{}:0] x,\n    input [{}:0] y,\n    output [{}:0] z\n);\n",
+        bits.saturating_sub(1),
+        bits.saturating_sub(1),
+        z_bits.saturating_sub(1)
+    );
+    let mut internal = gates
+        .iter()
+        .map(|gate| gate.out.as_str())
+        .filter(|wire| wire_bit(wire).is_none())
         .collect::<Vec<_>>();
-    swaps_out.sort();
-    Ok(swaps_out.join(","))
+    internal.sort();
+    for wire in internal {
+        verilog += &format!("    wire {};\n", net(wire));
+    }
+    for (i, gate) in gates.iter().enumerate() {
+        verilog += &format!(
+            "    {} g{i}({}, {}, {});\n",
+            match gate.gate_type {
+                GateType::And => "and",
+                GateType::Xor => "xor",
+                GateType::Or => "or",
+            },
+            net(&gate.out),
+            net(&gate.in1),
+            net(&gate.in2)
+        );
+    }
+    verilog += "endmodule\n";
+    verilog
 }
 
 fn find_gate_from_two_in(
@@ -181,13 +784,12 @@ fn find_gate_from_one_in(gates: &[Gate], in1: &str, gate_type: GateType) -> Opti
         .map(|(i, _)| i)
 }
 
-fn find_gates_from_out(gates: &[Gate], out: &str) -> usize {
+fn find_gate_from_out(gates: &[Gate], out: &str) -> Option<usize> {
     gates
         .iter()
         .enumerate()
         .find(|(_, gate)| gate.out == out)
         .map(|(i, _)| i)
-        .unwrap()
 }
 
 fn swap_gates(gates: &mut [Gate], i1: usize, i2: usize) {
@@ -196,113 +798,165 @@ fn swap_gates(gates: &mut [Gate], i1: usize, i2: usize) {
     l[l_i].swap_outs(&mut r[0]);
 }
 
-fn build_adder(
-    x_y_len: usize,
-    gates: &mut [Gate],
-    used_gates: &mut HashSet<usize>,
-    swaps: &mut HashSet<usize>,
-    mut carry_i: usize,
-    x_y_i: usize,
-) {
-    if x_y_i == x_y_len {
-        return;
-    }
-
-    let x_y_and_i = find_gate_from_two_in(
-        gates,
-        &format!("x{x_y_i:02}"),
-        &format!("y{x_y_i:02}"),
-        GateType::And,
-    )
-    .unwrap();
-    used_gates.insert(x_y_and_i);
-    let x_y_xor_i = find_gate_from_two_in(
-        gates,
-        &format!("x{x_y_i:02}"),
-        &format!("y{x_y_i:02}"),
-        GateType::Xor,
-    )
-    .unwrap();
-    used_gates.insert(x_y_xor_i);
-
-    if let Some(z_out_i) = find_gate_from_two_in(
-        gates,
-        &gates[x_y_xor_i].out,
-        &gates[carry_i].out,
-        GateType::Xor,
-    ) {
-        used_gates.insert(z_out_i);
-        if gates[z_out_i].out != format!("z{x_y_i:02}") {
-            // z_out has incorrect out, swap it
-            let swap_i = find_gates_from_out(gates, &format!("z{x_y_i:02}"));
-            assert!(swaps.insert(swap_i));
-            assert!(swaps.insert(z_out_i));
-            swap_gates(gates, z_out_i, swap_i);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ripple carry adder of `bits` bits, with `a` the input XOR, `b` the input AND, `c` the
+    /// carry AND and `d` the carry OR of each bit
+    fn adder(bits: usize) -> Vec<Gate> {
+        let gate = |gate_type, in1: &str, in2: &str, out: &str| Gate {
+            gate_type,
+            in1: in1.to_string(),
+            in2: in2.to_string(),
+            out: out.to_string(),
+        };
+        let mut gates = vec![
+            gate(GateType::Xor, "x00", "y00", "z00"),
+            gate(GateType::And, "x00", "y00", "d00"),
+        ];
+        for bit in 1..bits {
+            let wire = |name: char| format!("{name}{bit:02}");
+            let carry = format!("d{:02}", bit - 1);
+            let carry_out = if bit == bits - 1 {
+                format!("z{bits:02}")
+            } else {
+                wire('d')
+            };
+            gates.extend([
+                gate(GateType::Xor, &wire('x'), &wire('y'), &wire('a')),
+                gate(GateType::And, &wire('x'), &wire('y'), &wire('b')),
+                gate(GateType::Xor, &wire('a'), &carry, &wire('z')),
+                gate(GateType::And, &wire('a'), &carry, &wire('c')),
+                gate(GateType::Or, &wire('b'), &wire('c'), &carry_out),
+            ]);
         }
-    } else {
-        // either carry or x_y_xor has incorrect out - need swap
-        let z_out_i = find_gates_from_out(gates, &format!("z{x_y_i:02}"));
-        let z_out = &gates[z_out_i];
-        if let Some(other_in) = z_out.match_in(&gates[carry_i].out) {
-            // carry correct, swap x_y_xor.out for other_in
-            let other_in_i = find_gates_from_out(gates, other_in);
-            assert!(swaps.insert(other_in_i));
-            assert!(swaps.insert(x_y_xor_i));
-            swap_gates(gates, other_in_i, x_y_xor_i);
-        } else if let Some(other_in) = z_out.match_in(&gates[x_y_xor_i].out) {
-            // x_y_xor correct, swap carry.out for other_in
-            let other_in_i = find_gates_from_out(gates, other_in);
-            assert!(swaps.insert(other_in_i));
-            assert!(swaps.insert(carry_i));
-            swap_gates(gates, other_in_i, carry_i);
-        } else {
-            panic!("gate with z_out doenst match either in")
+        gates
+    }
+
+    fn check_swap(out1: &str, out2: &str, faults: &[AdderFault]) {
+        let gates = swap_outputs(&adder(4), &[(out1.to_string(), out2.to_string())]).unwrap();
+        assert_eq!(verify_adder(&gates, 4), faults);
+        let mut swaps = [out1, out2];
+        swaps.sort();
+        assert_eq!(fault_swaps(faults), swaps);
+        assert!(check_random(&adder(4), 4, 256, 0).unwrap().is_none());
+        assert_eq!(
+            validate_swaps(&gates, 4, &fault_swaps(faults), 256, 0).unwrap(),
+            Some(vec![(swaps[0].to_string(), swaps[1].to_string())])
+        );
+    }
+
+    fn miswired(bit: usize, gate: AdderGate, out: &str, expected: &str) -> AdderFault {
+        AdderFault::Miswired {
+            bit,
+            gate,
+            out: out.to_string(),
+            expected: expected.to_string(),
         }
     }
 
-    // carry and x_y_xor are correct, gate "and" them
-    let carry_and_i = find_gate_from_two_in(
-        gates,
-        &gates[carry_i].out,
-        &gates[x_y_xor_i].out,
-        GateType::And,
-    )
-    .unwrap();
-    used_gates.insert(carry_and_i);
-
-    if let Some(next_carry_i) = find_gate_from_two_in(
-        gates,
-        &gates[x_y_and_i].out,
-        &gates[carry_and_i].out,
-        GateType::Or,
-    ) {
-        used_gates.insert(next_carry_i);
-        carry_i = next_carry_i;
-    } else {
-        // either x_y_and_i out or carry_and_i out is wrong
-        let next_carry_i = match (
-            find_gate_from_one_in(gates, &gates[x_y_and_i].out, GateType::Or),
-            find_gate_from_one_in(gates, &gates[carry_and_i].out, GateType::Or),
-        ) {
-            (None, Some(other_i)) => {
-                // carry_add_i out found in Or, swap x_y_and_i out with other
-                assert!(swaps.insert(other_i));
-                assert!(swaps.insert(x_y_and_i));
-                swap_gates(gates, other_i, x_y_and_i);
-                other_i
-            }
-            (Some(other_i), None) => {
-                // x_y_and_i out found in Or, carry_and_i out with other
-                assert!(swaps.insert(other_i));
-                assert!(swaps.insert(carry_and_i));
-                swap_gates(gates, other_i, carry_and_i);
-                other_i
-            }
-            _ => panic!("expecting single match"),
-        };
-        used_gates.insert(next_carry_i);
-        carry_i = next_carry_i;
+    #[test]
+    fn add_lanes() {
+        let circuit = Circuit::compile(&adder(4)).unwrap();
+        let xys = (0..64).map(|i| (i % 16, i / 4)).collect::<Vec<_>>();
+        let zs = circuit.add(&xys);
+        assert_eq!(zs.len(), 64);
+        for ((x, y), z) in xys.into_iter().zip(zs) {
+            assert_eq!(z, x + y, "{x} + {y}");
+        }
+        assert_eq!(circuit.add(&[(15, 15), (3, 5)]), [30, 8]);
+    }
+
+    #[test]
+    fn compile_errors() {
+        let gates = swap_outputs(&adder(4), &[("a02".to_string(), "z02".to_string())]).unwrap();
+        assert_eq!(
+            Circuit::compile(&gates).unwrap_err().to_string(),
+            "gates form a cycle, unresolved wires a02,c02,c03,d02,z03,z04"
+        );
+
+        let mut gates = adder(4);
+        gates[0].out = "z64".to_string();
+        assert!(Circuit::compile(&gates)
+            .unwrap_err()
+            .to_string()
+            .contains("z64"));
+        gates[0].out = "z63".to_string();
+        assert!(Circuit::compile(&gates).is_ok());
     }
 
-    build_adder(x_y_len, gates, used_gates, swaps, carry_i, x_y_i + 1);
+    #[test]
+    fn verilog_names() {
+        let mut gates = adder(2);
+        gates[1].out = "and".to_string();
+        gates[4].in2 = "and".to_string();
+        gates[5].in2 = "and".to_string();
+        let verilog = to_verilog(&gates, 2);
+        assert!(verilog.contains("    wire \\and ;\n"), "{verilog}");
+        assert!(
+            verilog.contains("    and g1(\\and , x[0], y[0]);\n"),
+            "{verilog}"
+        );
+        assert!(
+            verilog.contains("    xor g4(z[1], \\a01 , \\and );\n"),
+            "{verilog}"
+        );
+        assert!(!verilog.contains(" and,"), "{verilog}");
+    }
+
+    #[test]
+    fn no_faults() {
+        assert_eq!(verify_adder(&adder(4), 4), []);
+        assert_eq!(check_random(&adder(4), 4, 256, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn z_with_carry_or() {
+        check_swap("z02", "d02", &[miswired(2, AdderGate::Sum, "d02", "z02")]);
+    }
+
+    #[test]
+    fn z_with_sum_xor() {
+        check_swap("z01", "z02", &[miswired(1, AdderGate::Sum, "z02", "z01")]);
+    }
+
+    #[test]
+    fn input_xor_with_sum_xor() {
+        check_swap(
+            "a02",
+            "z02",
+            &[miswired(2, AdderGate::InputXor, "z02", "a02")],
+        );
+    }
+
+    #[test]
+    fn input_and_with_carry_and() {
+        // both ANDs of a bit feed the same OR, so swapping them within a bit is no fault
+        let gates = swap_outputs(&adder(4), &[("b02".to_string(), "c02".to_string())]).unwrap();
+        assert_eq!(verify_adder(&gates, 4), []);
+        check_swap(
+            "b02",
+            "c01",
+            &[miswired(1, AdderGate::CarryAnd, "b02", "c01")],
+        );
+    }
+
+    #[test]
+    fn input_and_with_input_xor() {
+        check_swap(
+            "b02",
+            "a02",
+            &[miswired(2, AdderGate::InputXor, "b02", "a02")],
+        );
+    }
+
+    #[test]
+    fn bit_zero_carry() {
+        check_swap(
+            "d00",
+            "b01",
+            &[miswired(1, AdderGate::InputAnd, "b01", "d00")],
+        );
+    }
 }
@@ -1,6 +1,75 @@
+use anyhow::Result;
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day24, part1)]
+#[derive(Clone, Debug, clap::ValueEnum)]
+enum Export {
+    Dot,
+    Verilog,
+}
+
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// print the gates as a graphviz dot graph or verilog module instead of solving
+    #[arg(long, value_enum)]
+    export: Option<Export>,
+    /// check the gates add with this many random inputs, then check the part two swaps
+    #[arg(long, value_name = "TRIALS")]
+    fuzz: Option<usize>,
+    /// comma separated output wires to check with --fuzz instead of the part two swaps
+    #[arg(long, value_delimiter = ',', requires = "fuzz")]
+    swaps: Vec<String>,
+    /// seed for the --fuzz inputs
+    #[arg(long, default_value_t = 0, requires = "fuzz")]
+    seed: u64,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        let (wires, gates) = day24::parse_input(input)?;
+        let bits = day24::input_bits(&wires)?;
+        match &self.export {
+            Some(Export::Dot) => {
+                let swapped = day24::fault_swaps(&day24::verify_adder(&gates, bits));
+                print!("{}", day24::to_dot(&gates, &swapped));
+            }
+            Some(Export::Verilog) => print!("{}", day24::to_verilog(&gates, bits)),
+            None => {}
+        }
+        if let Some(trials) = self.fuzz {
+            fuzz(&gates, bits, trials, self)?;
+        }
+        Ok(self.export.is_some() || self.fuzz.is_some())
+    }
+}
+
+fn fuzz(gates: &[day24::Gate], bits: usize, trials: usize, cli: &Cli) -> Result<()> {
+    match day24::check_random(gates, bits, trials, cli.seed)? {
+        None => println!("gates add correctly for {trials} random inputs"),
+        Some(failure) => println!(
+            "lowest failing bit {} for x={} y={} z={}",
+            failure.bit, failure.x, failure.y, failure.z
+        ),
+    }
+    let swaps = if cli.swaps.is_empty() {
+        day24::fault_swaps(&day24::verify_adder(gates, bits))
+    } else {
+        cli.swaps.clone()
+    };
+    match day24::validate_swaps(gates, bits, &swaps, trials, cli.seed)? {
+        Some(pairs) => println!(
+            "swaps {} add correctly for {trials} random inputs",
+            pairs
+                .iter()
+                .map(|(out1, out2)| format!("{out1}<->{out2}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+        None => println!("no pairing of swaps {} adds correctly", swaps.join(",")),
+    }
+    Ok(())
+}
+
+#[aoc(day24, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day24::solve_one(input)
 }
@@ -1,36 +1,28 @@
 use anyhow::{anyhow, Result};
-use std::collections::{HashMap, HashSet};
 
-struct ParsedInput {
-    rules: HashMap<usize, HashSet<usize>>,
-    updates: Vec<Vec<usize>>,
+pub mod ordering;
+
+use ordering::{Rules, Verdict};
+
+pub struct ParsedInput {
+    pub rules: Rules,
+    pub updates: Vec<Vec<usize>>,
 }
 
-fn parse_input(input: &str) -> Result<ParsedInput> {
+pub fn parse_input(input: &str) -> Result<ParsedInput> {
     let (rules, updates) = input
         .split_once("\n\n")
         .ok_or_else(|| anyhow!("missing empty line"))?;
     Ok(ParsedInput {
-        rules: rules
-            .lines()
-            .map(|line| {
-                let (l, r) = line.split_once('|').ok_or_else(|| anyhow!("missing |"))?;
-                Ok((l.parse::<usize>()?, r.parse::<usize>()?))
-            })
-            .collect::<Result<Vec<_>>>()?
-            .into_iter()
-            .fold(HashMap::new(), |mut map, (l, r)| {
-                map.entry(l)
-                    .and_modify(|v: &mut HashSet<usize>| {
-                        v.insert(r);
-                    })
-                    .or_insert_with(|| {
-                        let mut set = HashSet::new();
-                        set.insert(r);
-                        set
-                    });
-                map
-            }),
+        rules: Rules::new(
+            rules
+                .lines()
+                .map(|line| {
+                    let (l, r) = line.split_once('|').ok_or_else(|| anyhow!("missing |"))?;
+                    Ok((l.parse::<usize>()?, r.parse::<usize>()?))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        ),
         updates: updates
             .lines()
             .map(|line| {
@@ -42,66 +34,34 @@ fn parse_input(input: &str) -> Result<ParsedInput> {
     })
 }
 
-pub fn solve_one(input: &str) -> Result<String> {
+/// each update's verdict, failing with the update on a cycle
+fn verdicts(input: &str) -> Result<Vec<(Vec<usize>, Verdict)>> {
     let ParsedInput { rules, updates } = parse_input(input)?;
-
-    Ok(updates
+    updates
         .into_iter()
-        .filter_map(|update| {
-            let mut seen = HashSet::new();
-            if update.iter().any(|v| {
-                if rules
-                    .get(v)
-                    .map(|after| seen.intersection(after).count() != 0)
-                    .unwrap_or(false)
-                {
-                    true
-                } else {
-                    seen.insert(*v);
-                    false
-                }
-            }) {
-                None
-            } else {
-                Some(update[update.len() / 2])
-            }
+        .map(|update| {
+            let verdict = ordering::verify(&rules, &update)
+                .map_err(|cycle| anyhow!("update {update:?}: {cycle}"))?;
+            Ok((update, verdict))
         })
+        .collect()
+}
+
+pub fn solve_one(input: &str) -> Result<String> {
+    Ok(verdicts(input)?
+        .into_iter()
+        .filter(|(_, verdict)| *verdict == Verdict::Ordered)
+        .map(|(update, _)| update[update.len() / 2])
         .sum::<usize>()
         .to_string())
 }
 
 pub fn solve_two(input: &str) -> Result<String> {
-    let ParsedInput { rules, updates } = parse_input(input)?;
-
-    Ok(updates
+    Ok(verdicts(input)?
         .into_iter()
-        .filter_map(|update| {
-            let mut seen = HashSet::new();
-            update.iter().find(|&v| {
-                if rules
-                    .get(v)
-                    .map(|after| seen.intersection(after).count() != 0)
-                    .unwrap_or(false)
-                {
-                    true
-                } else {
-                    seen.insert(*v);
-                    false
-                }
-            })?;
-            let mut update = update.clone();
-            (0..update.len() - 1).for_each(|i| {
-                (i + 1..update.len()).for_each(|j| {
-                    if rules
-                        .get(&update[j])
-                        .map(|after| after.contains(&update[i]))
-                        .unwrap_or(false)
-                    {
-                        update.swap(i, j);
-                    }
-                });
-            });
-            Some(update[update.len() / 2])
+        .filter_map(|(_, verdict)| match verdict {
+            Verdict::Ordered => None,
+            Verdict::Unordered(fixed) => Some(fixed[fixed.len() / 2]),
         })
         .sum::<usize>()
         .to_string())
@@ -1,6 +1,43 @@
+use anyhow::Result;
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day5, part1)]
+use day5::ordering::{self, Verdict};
+
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// print whether each update is ordered, its corrected order or the rule cycle among its
+    /// pages instead of solving
+    #[arg(long)]
+    check: bool,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        if !self.check {
+            return Ok(false);
+        }
+        let day5::ParsedInput { rules, updates } = day5::parse_input(input)?;
+        for update in updates {
+            let pages = join(&update);
+            match ordering::verify(&rules, &update) {
+                Ok(Verdict::Ordered) => println!("{pages}: ordered"),
+                Ok(Verdict::Unordered(fixed)) => println!("{pages}: should be {}", join(&fixed)),
+                Err(cycle) => println!("{pages}: {cycle}"),
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn join(pages: &[usize]) -> String {
+    pages
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[aoc(day5, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day5::solve_one(input)
 }
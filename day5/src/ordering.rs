@@ -0,0 +1,149 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// page ordering rules, each page mapped to the pages that must come after it
+#[derive(Clone, Debug, Default)]
+pub struct Rules {
+    after: HashMap<usize, HashSet<usize>>,
+}
+impl Rules {
+    pub fn new(rules: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        let mut after = HashMap::<_, HashSet<_>>::new();
+        for (l, r) in rules {
+            after.entry(l).or_default().insert(r);
+        }
+        Self { after }
+    }
+
+    /// whether a rule puts `l` before `r`
+    pub fn before(&self, l: usize, r: usize) -> bool {
+        self.after.get(&l).is_some_and(|after| after.contains(&r))
+    }
+
+    /// the rules between pages of the update, pages are expected to be distinct
+    pub fn induced(&self, pages: &[usize]) -> Graph {
+        let edges = pages
+            .iter()
+            .map(|l| {
+                (0..pages.len())
+                    .filter(|r| self.before(*l, pages[*r]))
+                    .collect()
+            })
+            .collect();
+        Graph {
+            pages: pages.to_vec(),
+            edges,
+        }
+    }
+}
+
+/// pages with edges by index into `pages`, from each page to those that must follow it
+#[derive(Clone, Debug)]
+pub struct Graph {
+    pages: Vec<usize>,
+    edges: Vec<Vec<usize>>,
+}
+impl Graph {
+    /// topological order of the pages by Kahn's algorithm
+    pub fn sort(&self) -> Result<Sorted, Cycle> {
+        let mut in_degree = vec![0; self.pages.len()];
+        for r in self.edges.iter().flatten() {
+            in_degree[*r] += 1;
+        }
+        let mut ready = (0..self.pages.len())
+            .filter(|p| in_degree[*p] == 0)
+            .collect::<Vec<_>>();
+        let mut order = vec![];
+        let mut total = true;
+        while let Some(p) = ready.pop() {
+            // a choice of page means another order would do as well
+            total &= ready.is_empty();
+            order.push(self.pages[p]);
+            for r in &self.edges[p] {
+                in_degree[*r] -= 1;
+                if in_degree[*r] == 0 {
+                    ready.push(*r);
+                }
+            }
+        }
+        if order.len() < self.pages.len() {
+            return Err(self.cycle(&in_degree));
+        }
+        Ok(Sorted { order, total })
+    }
+
+    /// a cycle among the pages Kahn's algorithm left with edges into them
+    fn cycle(&self, in_degree: &[usize]) -> Cycle {
+        let mut preds = vec![vec![]; self.pages.len()];
+        for (l, edges) in self.edges.iter().enumerate() {
+            for r in edges {
+                preds[*r].push(l);
+            }
+        }
+        // every page left has a predecessor left, so walking back must repeat a page
+        let left = |p: &&usize| in_degree[**p] > 0;
+        let mut walk = vec![(0..self.pages.len())
+            .find(|p| in_degree[*p] > 0)
+            .expect("a page is left")];
+        loop {
+            let last = walk[walk.len() - 1];
+            let pred = *preds[last]
+                .iter()
+                .find(left)
+                .expect("a predecessor is left");
+            if let Some(i) = walk.iter().position(|p| *p == pred) {
+                let mut chain = walk[i..]
+                    .iter()
+                    .rev()
+                    .map(|p| self.pages[*p])
+                    .collect::<Vec<_>>();
+                chain.push(chain[0]);
+                return Cycle(chain);
+            }
+            walk.push(pred);
+        }
+    }
+}
+
+/// pages in an order satisfying the rules, `total` if it is the only such order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sorted {
+    pub order: Vec<usize>,
+    pub total: bool,
+}
+
+/// pages each required before the next, the first page repeated at the end
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle(pub Vec<usize>);
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let chain = self.0.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        write!(f, "rules form a cycle {}", chain.join(" -> "))
+    }
+}
+impl std::error::Error for Cycle {}
+
+/// whether an update follows the rules, with the corrected order if not
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Ordered,
+    Unordered(Vec<usize>),
+}
+
+/// check the update against the rules between its pages
+pub fn verify(rules: &Rules, update: &[usize]) -> Result<Verdict, Cycle> {
+    let sorted = rules.induced(update).sort()?;
+    let ordered = if sorted.total {
+        sorted.order == update
+    } else {
+        update
+            .iter()
+            .enumerate()
+            .all(|(i, r)| update[i + 1..].iter().all(|l| !rules.before(*l, *r)))
+    };
+    Ok(if ordered {
+        Verdict::Ordered
+    } else {
+        Verdict::Unordered(sorted.order)
+    })
+}
@@ -1,15 +1,16 @@
-use anyhow::{anyhow, Result};
-use std::{cell::Cell, collections::HashSet};
+use anyhow::{anyhow, bail, Result};
 
-#[derive(Clone, Hash, Eq, PartialEq)]
-enum Dir {
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum Dir {
     N,
-    S,
     E,
+    S,
     W,
 }
 impl Dir {
-    fn turn(&self) -> Self {
+    const ALL: [Dir; 4] = [Dir::N, Dir::E, Dir::S, Dir::W];
+
+    pub fn turn(&self) -> Self {
         match self {
             Dir::N => Dir::E,
             Dir::S => Dir::W,
@@ -17,137 +18,248 @@ impl Dir {
             Dir::W => Dir::N,
         }
     }
-}
 
-struct ParsedInput {
-    guard: (usize, usize),
-    objects: HashSet<(usize, usize)>,
-    max_i_j: (usize, usize),
+    pub fn is_vertical(&self) -> bool {
+        matches!(self, Dir::N | Dir::S)
+    }
 }
 
-pub fn solve_one(input: &str) -> Result<String> {
-    let ParsedInput {
-        guard,
-        objects,
-        max_i_j: (max_i, max_j),
-    } = parse_input(input)?;
-
-    Ok(get_steps(guard, max_i, max_j, &objects).len().to_string())
+/// lab map, cells are indexed `i * width + j`
+pub struct Lab {
+    pub width: usize,
+    pub height: usize,
+    pub walls: Vec<bool>,
+    pub guard: usize,
+    /// for each cell and direction, the cell the guard stops at before the next wall, `None`
+    /// if it walks off the map
+    jumps: Vec<[Option<usize>; 4]>,
 }
 
-fn parse_input(input: &str) -> Result<ParsedInput> {
-    let guard = &Cell::new(None);
-    let objects = input
+pub fn parse_input(input: &str) -> Result<Lab> {
+    let width = input
         .lines()
-        .enumerate()
-        .flat_map(|(i, line)| {
-            line.chars()
-                .enumerate()
-                .filter_map(move |(j, char)| match char {
-                    '#' => Some((i, j)),
-                    '^' => {
-                        guard.replace(Some((i, j)));
-                        None
-                    }
-                    _ => None,
-                })
-        })
-        .collect::<HashSet<(usize, usize)>>();
-    let guard = guard.get().ok_or_else(|| anyhow!("missing ^"))?;
-    let max_i_j = (input.lines().count(), input.lines().next().unwrap().len());
-    Ok(ParsedInput {
+        .next()
+        .ok_or_else(|| anyhow!("empty map"))?
+        .len();
+    let mut walls = vec![];
+    let mut guard = None;
+    for line in input.lines() {
+        if line.len() != width {
+            bail!("lines differ in length");
+        }
+        for c in line.chars() {
+            if c == '^' {
+                guard = Some(walls.len());
+            }
+            walls.push(c == '#');
+        }
+    }
+    let guard = guard.ok_or_else(|| anyhow!("missing ^"))?;
+    let mut lab = Lab {
+        width,
+        height: walls.len() / width,
+        walls,
         guard,
-        objects,
-        max_i_j,
-    })
+        jumps: vec![],
+    };
+    lab.jumps = lab.build_jumps();
+    Ok(lab)
 }
 
-fn get_steps(
-    mut guard: (usize, usize),
-    max_i: usize,
-    max_j: usize,
-    objects: &HashSet<(usize, usize)>,
-) -> HashSet<(usize, usize)> {
-    let mut dir = Dir::N;
-    let mut steps = HashSet::new();
-    steps.insert(guard);
-
-    while let Some(next_guard) = match dir {
-        Dir::N => guard.0.checked_sub(1).map(|i| (i, guard.1)),
-        Dir::S => guard.0.checked_add(1).map(|i| (i, guard.1)),
-        Dir::E => guard.1.checked_add(1).map(|j| (guard.0, j)),
-        Dir::W => guard.1.checked_sub(1).map(|j| (guard.0, j)),
-    } {
-        if next_guard.0 >= max_i || next_guard.1 >= max_j {
-            break;
+impl Lab {
+    pub fn pos(&self, cell: usize) -> (usize, usize) {
+        (cell / self.width, cell % self.width)
+    }
+
+    /// the cell next to `cell` in direction `dir`, if on the map
+    pub fn step(&self, cell: usize, dir: Dir) -> Option<usize> {
+        let (i, j) = self.pos(cell);
+        match dir {
+            Dir::N => i.checked_sub(1).map(|i| (i, j)),
+            Dir::S => (i + 1 < self.height).then_some((i + 1, j)),
+            Dir::E => (j + 1 < self.width).then_some((i, j + 1)),
+            Dir::W => j.checked_sub(1).map(|j| (i, j)),
         }
-        if objects.contains(&next_guard) {
-            dir = dir.turn();
-            continue;
+        .map(|(i, j)| i * self.width + j)
+    }
+
+    fn build_jumps(&self) -> Vec<[Option<usize>; 4]> {
+        let mut jumps = vec![[None; 4]; self.walls.len()];
+        for dir in Dir::ALL {
+            // fill cells nearest the edge being walked towards first so the next cell is known
+            let cells = (0..self.walls.len()).collect::<Vec<_>>();
+            let cells: Box<dyn Iterator<Item = &usize>> = match dir {
+                Dir::N | Dir::W => Box::new(cells.iter()),
+                Dir::S | Dir::E => Box::new(cells.iter().rev()),
+            };
+            for cell in cells {
+                jumps[*cell][dir as usize] = match self.step(*cell, dir) {
+                    None => None,
+                    Some(next) if self.walls[next] => Some(*cell),
+                    Some(next) => jumps[next][dir as usize],
+                };
+            }
         }
-        guard = next_guard;
-        steps.insert(guard);
+        jumps
     }
-    steps
-}
 
-pub fn solve_two(input: &str) -> Result<String> {
-    let ParsedInput {
-        guard,
-        mut objects,
-        max_i_j: (max_i, max_j),
-    } = parse_input(input)?;
+    /// where the guard walking from `cell` in direction `dir` stops, as for the jump table but
+    /// with an extra wall at `obstruction`
+    fn jump(&self, cell: usize, dir: Dir, obstruction: usize) -> Option<usize> {
+        let stop = self.jumps[cell][dir as usize];
+        let ((i, j), (oi, oj)) = (self.pos(cell), self.pos(obstruction));
+        let ahead = match dir {
+            Dir::N => oj == j && oi < i && stop.is_none_or(|s| oi >= self.pos(s).0),
+            Dir::S => oj == j && oi > i && stop.is_none_or(|s| oi <= self.pos(s).0),
+            Dir::E => oi == i && oj > j && stop.is_none_or(|s| oj <= self.pos(s).1),
+            Dir::W => oi == i && oj < j && stop.is_none_or(|s| oj >= self.pos(s).1),
+        };
+        if ahead {
+            self.step(obstruction, dir.turn().turn())
+        } else {
+            stop
+        }
+    }
 
-    let steps = get_steps(guard, max_i, max_j, &objects);
+    /// every position and direction of the guard in order until they leave the map, a turn
+    /// gives a second entry for the same cell
+    pub fn get_steps(&self) -> Result<Vec<(usize, Dir)>> {
+        let mut steps = vec![(self.guard, Dir::N)];
+        loop {
+            let (cell, dir) = steps[steps.len() - 1];
+            steps.push(match self.step(cell, dir) {
+                None => return Ok(steps),
+                Some(next) if self.walls[next] => (cell, dir.turn()),
+                Some(next) => (next, dir),
+            });
+            if steps.len() > 4 * self.walls.len() {
+                bail!("guard never leaves the map");
+            }
+        }
+    }
 
-    Ok(steps
-        .into_iter()
-        .filter(|&step| {
-            if guard == step {
-                return false;
+    /// whether the guard starting at `from` loops with an extra wall at `obstruction`
+    pub fn is_loop(&self, from: (usize, Dir), obstruction: usize) -> bool {
+        let mut seen = vec![0u64; (4 * self.walls.len()).div_ceil(64)];
+        let (mut cell, mut dir) = from;
+        while let Some(stop) = self.jump(cell, dir, obstruction) {
+            let state = 4 * stop + dir as usize;
+            if seen[state / 64] & (1 << (state % 64)) != 0 {
+                return true;
             }
+            seen[state / 64] |= 1 << (state % 64);
+            (cell, dir) = (stop, dir.turn());
+        }
+        false
+    }
 
-            objects.insert(step);
-            let l = is_loop(guard, max_i, max_j, &objects);
-            objects.remove(&step);
-            l
-        })
-        .count()
-        .to_string())
+    /// cells on the guard's path where one obstruction makes them loop, each with the state
+    /// the guard is in just before first walking into it
+    fn candidates(&self, steps: &[(usize, Dir)]) -> Vec<(usize, (usize, Dir))> {
+        let mut visited = vec![false; self.walls.len()];
+        visited[self.guard] = true;
+        steps
+            .windows(2)
+            .filter(|w| {
+                !visited[w[1].0] && {
+                    visited[w[1].0] = true;
+                    true
+                }
+            })
+            .map(|w| (w[1].0, w[0]))
+            .collect()
+    }
+
+    /// every cell where an obstruction makes the guard loop
+    #[cfg(not(feature = "rayon"))]
+    pub fn loop_obstructions(&self) -> Result<Vec<usize>> {
+        Ok(self
+            .candidates(&self.get_steps()?)
+            .into_iter()
+            .filter(|(obstruction, from)| self.is_loop(*from, *obstruction))
+            .map(|(obstruction, _)| obstruction)
+            .collect())
+    }
+
+    /// every cell where an obstruction makes the guard loop
+    #[cfg(feature = "rayon")]
+    pub fn loop_obstructions(&self) -> Result<Vec<usize>> {
+        use rayon::prelude::*;
+
+        Ok(self
+            .candidates(&self.get_steps()?)
+            .into_par_iter()
+            .filter(|(obstruction, from)| self.is_loop(*from, *obstruction))
+            .map(|(obstruction, _)| obstruction)
+            .collect())
+    }
 }
 
-fn is_loop(
-    mut guard: (usize, usize),
-    max_i: usize,
-    max_j: usize,
-    objects: &HashSet<(usize, usize)>,
-) -> bool {
-    let mut dir = Dir::N;
-    let mut steps = HashSet::new();
-    let mut steps_dir = HashSet::new();
-    steps.insert(guard);
-    steps_dir.insert((guard, dir.clone()));
-
-    while let Some(next_guard) = match dir {
-        Dir::N => guard.0.checked_sub(1).map(|i| (i, guard.1)),
-        Dir::S => guard.0.checked_add(1).map(|i| (i, guard.1)),
-        Dir::E => guard.1.checked_add(1).map(|j| (guard.0, j)),
-        Dir::W => guard.1.checked_sub(1).map(|j| (guard.0, j)),
-    } {
-        if next_guard.0 >= max_i || next_guard.1 >= max_j {
-            break;
-        }
-        if objects.contains(&next_guard) {
-            dir = dir.turn();
+/// map with the guard's path drawn as in the puzzle, `|` and `-` for vertical and horizontal
+/// moves, `+` where they cross or the guard turns, and `O` where an obstruction makes a loop
+pub fn render(lab: &Lab) -> Result<Vec<Vec<char>>> {
+    let steps = lab.get_steps()?;
+    // vertical and horizontal moves through each cell
+    let mut moves = vec![(false, false); lab.walls.len()];
+    for w in steps.windows(2) {
+        let ((cell, dir), (next, next_dir)) = (w[0], w[1]);
+        if cell == next {
+            moves[cell] = (true, true);
             continue;
         }
-        if steps_dir.contains(&(next_guard, dir.clone())) {
-            return true;
+        for (cell, dir) in [(cell, dir), (next, next_dir)] {
+            if dir.is_vertical() {
+                moves[cell].0 = true;
+            } else {
+                moves[cell].1 = true;
+            }
         }
-        guard = next_guard;
-        steps.insert(guard);
-        steps_dir.insert((guard, dir.clone()));
     }
+    let mut grid = moves
+        .iter()
+        .zip(&lab.walls)
+        .map(|(moves, wall)| match (wall, moves) {
+            (true, _) => '#',
+            (_, (true, true)) => '+',
+            (_, (true, false)) => '|',
+            (_, (false, true)) => '-',
+            _ => '.',
+        })
+        .collect::<Vec<_>>();
+    for cell in lab.loop_obstructions()? {
+        grid[cell] = 'O';
+    }
+    grid[lab.guard] = '^';
+    Ok(grid.chunks(lab.width).map(|row| row.to_vec()).collect())
+}
+
+pub fn to_text(grid: &[Vec<char>]) -> String {
+    grid.iter()
+        .map(|l| l.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// colour of a rendered map cell in images
+pub fn colour(c: &char) -> [u8; 3] {
+    match c {
+        '#' => [128, 128, 128],
+        '|' | '-' | '+' => [60, 110, 220],
+        'O' => [220, 40, 40],
+        '^' => [40, 200, 40],
+        _ => [0, 0, 0],
+    }
+}
+
+pub fn solve_one(input: &str) -> Result<String> {
+    let lab = parse_input(input)?;
+    let mut visited = vec![false; lab.walls.len()];
+    for (cell, _) in lab.get_steps()? {
+        visited[cell] = true;
+    }
+    Ok(visited.into_iter().filter(|v| *v).count().to_string())
+}
 
-    false
+pub fn solve_two(input: &str) -> Result<String> {
+    Ok(parse_input(input)?.loop_obstructions()?.len().to_string())
 }
@@ -1,6 +1,37 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day6, part1)]
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// draw the guard's path and every obstruction making a loop instead of solving
+    #[arg(long)]
+    render: bool,
+    /// write the render as a ppm image instead of printing it
+    #[arg(long, value_name = "FILE", requires = "render")]
+    image: Option<PathBuf>,
+    /// pixels per map cell in the image
+    #[arg(long, default_value_t = 4)]
+    cell: usize,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        if !self.render {
+            return Ok(false);
+        }
+        let grid = day6::render(&day6::parse_input(input)?)?;
+        match &self.image {
+            Some(file) => fs::write(file, utils::ppm::from_grid(&grid, self.cell, day6::colour))?,
+            None => println!("{}", day6::to_text(&grid)),
+        }
+        Ok(true)
+    }
+}
+
+#[aoc(day6, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day6::solve_one(input)
 }
@@ -1,116 +1,181 @@
-use anyhow::{anyhow, Result};
+use std::fmt::Write;
 
-pub fn solve_one(input: &str) -> Result<String> {
-    let lines = parse_input(input)?;
-    Ok(lines
-        .into_iter()
-        .filter(|(total, list)| {
-            can_equal(
-                *total,
-                list,
-                1,
-                list[0],
-                list.iter().skip(1).filter(|&v| *v != 1).sum::<u64>(),
-            )
-        })
-        .map(|(total, _)| total)
-        .sum::<u64>()
-        .to_string())
+use anyhow::{anyhow, bail, Result};
+
+/// binary operator in a calibration equation, evaluated left to right
+pub trait Operator {
+    fn symbol(&self) -> &'static str;
+    /// `l op r`, `None` on overflow
+    fn apply(&self, l: u64, r: u64) -> Option<u64>;
+    /// the `l` with `l op r == total`
+    fn unapply(&self, total: u64, r: u64) -> Inverse;
+}
+
+/// left operands giving a total, see [`Operator::unapply`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Inverse {
+    None,
+    Exactly(u64),
+    /// every `l` gives the total
+    Any,
+}
+impl From<Option<u64>> for Inverse {
+    fn from(l: Option<u64>) -> Self {
+        l.map_or(Inverse::None, Inverse::Exactly)
+    }
+}
+
+pub struct Add;
+impl Operator for Add {
+    fn symbol(&self) -> &'static str {
+        "+"
+    }
+    fn apply(&self, l: u64, r: u64) -> Option<u64> {
+        l.checked_add(r)
+    }
+    fn unapply(&self, total: u64, r: u64) -> Inverse {
+        total.checked_sub(r).into()
+    }
+}
+
+pub struct Mul;
+impl Operator for Mul {
+    fn symbol(&self) -> &'static str {
+        "*"
+    }
+    fn apply(&self, l: u64, r: u64) -> Option<u64> {
+        l.checked_mul(r)
+    }
+    fn unapply(&self, total: u64, r: u64) -> Inverse {
+        match (total, r) {
+            (0, 0) => Inverse::Any,
+            (_, 0) => Inverse::None,
+            _ => total.is_multiple_of(r).then(|| total / r).into(),
+        }
+    }
+}
+
+/// concatenation of the decimal digits
+pub struct Cat;
+impl Cat {
+    /// power of ten above `r`
+    fn tens(r: u64) -> Option<u64> {
+        let mut tens = 10u64;
+        while r / tens > 0 {
+            tens = tens.checked_mul(10)?;
+        }
+        Some(tens)
+    }
+}
+impl Operator for Cat {
+    fn symbol(&self) -> &'static str {
+        "||"
+    }
+    fn apply(&self, l: u64, r: u64) -> Option<u64> {
+        l.checked_mul(Self::tens(r)?)?.checked_add(r)
+    }
+    fn unapply(&self, total: u64, r: u64) -> Inverse {
+        Self::tens(r)
+            .filter(|tens| total % tens == r)
+            .map(|tens| total / tens)
+            .into()
+    }
 }
 
-fn parse_input(input: &str) -> Result<Vec<(u64, Vec<u64>)>> {
+pub const PART_ONE: [&dyn Operator; 2] = [&Add, &Mul];
+pub const PART_TWO: [&dyn Operator; 3] = [&Add, &Mul, &Cat];
+
+pub fn parse_input(input: &str) -> Result<Vec<(u64, Vec<u64>)>> {
     input
         .lines()
         .map(|line| {
             let (total, list) = line
                 .split_once(": ")
                 .ok_or_else(|| anyhow!("missing ': ' in line"))?;
-            Ok((
-                total.parse()?,
-                list.split_whitespace()
-                    .map(|v| Ok(v.parse()?))
-                    .collect::<Result<Vec<_>>>()?,
-            ))
+            let list = list
+                .split_whitespace()
+                .map(|v| Ok(v.parse()?))
+                .collect::<Result<Vec<_>>>()?;
+            if list.is_empty() {
+                bail!("no numbers after ': '");
+            }
+            Ok((total.parse()?, list))
         })
         .collect()
 }
 
-fn can_equal(total: u64, list: &[u64], pos: usize, cur: u64, rem: u64) -> bool {
-    if pos >= list.len() {
-        return total == cur;
-    } else if total == cur + rem {
-        return true;
-    } else if total < cur + rem {
-        return false;
+/// operators between the numbers making `total`, found by undoing the last operator first
+pub fn equation<'a>(
+    total: u64,
+    list: &[u64],
+    ops: &[&'a dyn Operator],
+) -> Option<Vec<&'a dyn Operator>> {
+    let (last, rest) = list.split_last()?;
+    if rest.is_empty() {
+        return (total == *last).then(Vec::new);
     }
+    ops.iter().find_map(|op| {
+        let mut found = match op.unapply(total, *last) {
+            Inverse::None => None,
+            Inverse::Exactly(l) => equation(l, rest, ops),
+            Inverse::Any => any_equation(rest[0], &rest[1..], ops),
+        }?;
+        found.push(*op);
+        Some(found)
+    })
+}
 
-    can_equal(
-        total,
-        list,
-        pos + 1,
-        cur + list[pos],
-        if list[pos] == 1 { rem } else { rem - list[pos] },
-    ) || can_equal(
-        total,
-        list,
-        pos + 1,
-        cur * list[pos],
-        if list[pos] == 1 { rem } else { rem - list[pos] },
-    )
+/// operators between the numbers that evaluate without overflowing, to any value, found left
+/// to right from `cur`
+fn any_equation<'a>(
+    cur: u64,
+    list: &[u64],
+    ops: &[&'a dyn Operator],
+) -> Option<Vec<&'a dyn Operator>> {
+    let Some((next, rest)) = list.split_first() else {
+        return Some(vec![]);
+    };
+    ops.iter().find_map(|op| {
+        let mut found = any_equation(op.apply(cur, *next)?, rest, ops)?;
+        found.insert(0, *op);
+        Some(found)
+    })
 }
 
-pub fn solve_two(input: &str) -> Result<String> {
-    let lines = parse_input(input)?;
-    Ok(lines
-        .into_iter()
-        .filter(|(total, list)| {
-            can_equal_with_cat(
-                *total,
-                list,
-                1,
-                list[0],
-                list.iter().skip(1).filter(|&v| *v != 1).sum::<u64>(),
-            )
-        })
-        .map(|(total, _)| total)
-        .sum::<u64>()
-        .to_string())
+/// value of the numbers with the operators between them, left to right, none if there are
+/// no numbers
+pub fn eval(list: &[u64], ops: &[&dyn Operator]) -> Option<u64> {
+    let (first, rest) = list.split_first()?;
+    rest.iter()
+        .zip(ops)
+        .try_fold(*first, |cur, (r, op)| op.apply(cur, *r))
 }
 
-fn can_equal_with_cat(total: u64, list: &[u64], pos: usize, cur: u64, rem: u64) -> bool {
-    if pos >= list.len() {
-        return total == cur;
-    } else if total == cur + rem {
-        return true;
-    } else if total < cur + rem {
-        return false;
+/// equation as text, e.g. `292 = 11 + 6 * 16 + 20`, empty if there are no numbers
+pub fn show(total: u64, list: &[u64], ops: &[&dyn Operator]) -> String {
+    let Some((first, rest)) = list.split_first() else {
+        return String::new();
+    };
+    let mut out = format!("{total} = {first}");
+    for (r, op) in rest.iter().zip(ops) {
+        write!(out, " {} {r}", op.symbol()).unwrap();
     }
+    out
+}
+
+/// sum of the totals some equation with the operators can make
+pub fn calibration(input: &str, ops: &[&dyn Operator]) -> Result<u64> {
+    Ok(parse_input(input)?
+        .into_iter()
+        .filter(|(total, list)| equation(*total, list, ops).is_some())
+        .map(|(total, _)| total)
+        .sum())
+}
 
-    can_equal_with_cat(
-        total,
-        list,
-        pos + 1,
-        cur + list[pos],
-        if list[pos] == 1 { rem } else { rem - list[pos] },
-    ) || can_equal_with_cat(
-        total,
-        list,
-        pos + 1,
-        cur * list[pos],
-        if list[pos] == 1 { rem } else { rem - list[pos] },
-    ) || can_equal_with_cat(
-        total,
-        list,
-        pos + 1,
-        cat(cur, list[pos]),
-        if list[pos] == 1 { rem } else { rem - list[pos] },
-    )
+pub fn solve_one(input: &str) -> Result<String> {
+    Ok(calibration(input, &PART_ONE)?.to_string())
 }
 
-fn cat(l: u64, r: u64) -> u64 {
-    let mut tens = 10;
-    while (r / tens) > 0 {
-        tens *= 10;
-    }
-    (l * tens) + r
+pub fn solve_two(input: &str) -> Result<String> {
+    Ok(calibration(input, &PART_TWO)?.to_string())
 }
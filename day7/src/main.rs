@@ -1,6 +1,61 @@
+use anyhow::{bail, Result};
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day7, part1)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Op {
+    Add,
+    Mul,
+    Cat,
+}
+impl Op {
+    fn operator(self) -> &'static dyn day7::Operator {
+        match self {
+            Op::Add => &day7::Add,
+            Op::Mul => &day7::Mul,
+            Op::Cat => &day7::Cat,
+        }
+    }
+}
+
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// operators to calibrate with, prints the calibration result instead of solving
+    #[arg(long, value_enum, value_delimiter = ',')]
+    ops: Vec<Op>,
+    /// print the equation found for each line, with part two operators unless --ops is given
+    #[arg(long)]
+    equations: bool,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        if self.ops.is_empty() && !self.equations {
+            return Ok(false);
+        }
+        let ops = if self.ops.is_empty() {
+            day7::PART_TWO.to_vec()
+        } else {
+            self.ops.iter().map(|op| op.operator()).collect()
+        };
+        if self.equations {
+            for (total, list) in day7::parse_input(input)? {
+                match day7::equation(total, &list, &ops) {
+                    Some(found) => {
+                        if day7::eval(&list, &found) != Some(total) {
+                            bail!("equation for {total} does not evaluate to it");
+                        }
+                        println!("{}", day7::show(total, &list, &found))
+                    }
+                    None => println!("{total}: no equation"),
+                }
+            }
+        }
+        println!("calibration:\n{}", day7::calibration(input, &ops)?);
+        Ok(true)
+    }
+}
+
+#[aoc(day7, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day7::solve_one(input)
 }
@@ -1,144 +1,172 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashSet};
+use std::ops::{Add, Mul, Sub};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
-struct Input {
-    antennas: HashMap<char, Vec<(usize, usize)>>,
-    max_i_j: (usize, usize),
+/// grid position or offset as (row, column)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct V(pub isize, pub isize);
+impl Add for V {
+    type Output = V;
+    fn add(self, rhs: V) -> V {
+        V(self.0 + rhs.0, self.1 + rhs.1)
+    }
 }
-fn parse_input(input: &str) -> Input {
-    Input {
-        antennas: input
-            .lines()
-            .enumerate()
-            .flat_map(|(i, line)| {
-                line.chars()
-                    .enumerate()
-                    .filter(|&(_, char)| char != '.')
-                    .map(move |(j, char)| (i, j, char))
-            })
-            .fold(HashMap::new(), |mut map, (i, j, char)| {
-                map.entry(char)
-                    .and_modify(|vec: &mut Vec<_>| vec.push((i, j)))
-                    .or_insert_with(|| vec![(i, j)]);
-                map
-            }),
-        max_i_j: (input.lines().count(), input.lines().next().unwrap().len()),
+impl Sub for V {
+    type Output = V;
+    fn sub(self, rhs: V) -> V {
+        V(self.0 - rhs.0, self.1 - rhs.1)
+    }
+}
+impl Mul<isize> for V {
+    type Output = V;
+    fn mul(self, rhs: isize) -> V {
+        V(self.0 * rhs, self.1 * rhs)
+    }
+}
+impl V {
+    /// smallest grid step in the same direction, with how many of it make `self`
+    pub fn reduced(self) -> (V, isize) {
+        let g = gcd(self.0.unsigned_abs(), self.1.unsigned_abs()).max(1) as isize;
+        (V(self.0 / g, self.1 / g), g)
     }
 }
 
-pub fn solve_one(input: &str) -> Result<String> {
-    let Input { antennas, max_i_j } = parse_input(input);
-    Ok(antennas
-        .iter()
-        .flat_map(|(_, antennas)| {
-            (0..antennas.len() - 1)
-                .flat_map(|a| (a + 1..antennas.len()).map(move |b| (a, b)))
-                .flat_map(|(a, b)| {
-                    let mut antinodes = Vec::new();
-                    let (a_i, a_j) = antennas[a];
-                    let (b_i, b_j) = antennas[b];
-                    let i_diff = a_i.abs_diff(b_i);
-                    let j_diff = a_j.abs_diff(b_j);
-                    let (anti_a_i, anti_b_i) = if a_i < b_i {
-                        (
-                            a_i.checked_sub(i_diff),
-                            b_i.checked_add(i_diff).filter(|&i| i < max_i_j.0),
-                        )
-                    } else {
-                        (
-                            a_i.checked_add(i_diff).filter(|&i| i < max_i_j.0),
-                            b_i.checked_sub(i_diff),
-                        )
-                    };
-                    let (anti_a_j, anti_b_j) = if a_j < b_j {
-                        (
-                            a_j.checked_sub(j_diff),
-                            b_j.checked_add(j_diff).filter(|&j| j < max_i_j.1),
-                        )
-                    } else {
-                        (
-                            a_j.checked_add(j_diff).filter(|&j| j < max_i_j.1),
-                            b_j.checked_sub(j_diff),
-                        )
-                    };
-                    if let (Some(i), Some(j)) = (anti_a_i, anti_a_j) {
-                        antinodes.push((i, j));
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// which antinodes a pair of antennas makes
+///
+/// harmonic `k` is `k` steps beyond each antenna, away from the other, harmonic 0 being the
+/// antennas themselves and, with reduced steps, the grid points between them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Harmonics {
+    pub min: usize,
+    /// no limit but the map if `None`
+    pub max: Option<usize>,
+    /// step by the gcd-reduced difference rather than the difference between the antennas
+    pub reduce: bool,
+}
+pub const PART_ONE: Harmonics = Harmonics {
+    min: 1,
+    max: Some(1),
+    reduce: false,
+};
+pub const PART_TWO: Harmonics = Harmonics {
+    min: 0,
+    max: None,
+    reduce: true,
+};
+
+pub struct Map {
+    pub antennas: BTreeMap<char, Vec<V>>,
+    pub size: V,
+}
+impl Map {
+    pub fn contains(&self, v: V) -> bool {
+        (0..self.size.0).contains(&v.0) && (0..self.size.1).contains(&v.1)
+    }
+
+    /// antinodes of every pair of the antennas
+    pub fn antinodes(&self, antennas: &[V], harmonics: &Harmonics) -> HashSet<V> {
+        let mut antinodes = HashSet::new();
+        for (i, a) in antennas.iter().enumerate() {
+            for b in &antennas[i + 1..] {
+                let (step, steps) = if harmonics.reduce {
+                    (*b - *a).reduced()
+                } else {
+                    (*b - *a, 1)
+                };
+                for k in harmonics.min.. {
+                    if harmonics.max.is_some_and(|max| k > max) {
+                        break;
                     }
-                    if let (Some(i), Some(j)) = (anti_b_i, anti_b_j) {
-                        antinodes.push((i, j));
+                    let k = k as isize;
+                    let beyond = [*b + step * k, *a - step * k]
+                        .into_iter()
+                        .filter(|v| self.contains(*v))
+                        .collect::<Vec<_>>();
+                    if beyond.is_empty() {
+                        break;
                     }
-                    antinodes
-                })
-        })
-        .collect::<HashSet<(usize, usize)>>()
+                    antinodes.extend(beyond);
+                }
+                if harmonics.min == 0 {
+                    antinodes.extend((1..steps).map(|t| *a + step * t));
+                }
+            }
+        }
+        antinodes
+    }
+
+    /// antinodes of every frequency
+    pub fn all_antinodes(&self, harmonics: &Harmonics) -> HashSet<V> {
+        self.antennas
+            .values()
+            .flat_map(|antennas| self.antinodes(antennas, harmonics))
+            .collect()
+    }
+
+    /// map of one frequency's antennas and their antinodes as `#`
+    pub fn render(&self, frequency: char, harmonics: &Harmonics) -> String {
+        let antennas = self
+            .antennas
+            .get(&frequency)
+            .map_or(&[][..], |a| a.as_slice());
+        let antinodes = self.antinodes(antennas, harmonics);
+        (0..self.size.0)
+            .map(|i| {
+                (0..self.size.1)
+                    .map(|j| match V(i, j) {
+                        v if antennas.contains(&v) => frequency,
+                        v if antinodes.contains(&v) => '#',
+                        _ => '.',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+pub fn parse_input(input: &str) -> Result<Map> {
+    let mut antennas = BTreeMap::<_, Vec<_>>::new();
+    for (i, line) in input.lines().enumerate() {
+        for (j, c) in line.chars().enumerate() {
+            if c != '.' {
+                antennas
+                    .entry(c)
+                    .or_default()
+                    .push(V(i as isize, j as isize));
+            }
+        }
+    }
+    let width = input
+        .lines()
+        .next()
+        .ok_or_else(|| anyhow!("empty map"))?
+        .len();
+    Ok(Map {
+        antennas,
+        size: V(input.lines().count() as isize, width as isize),
+    })
+}
+
+pub fn solve_one(input: &str) -> Result<String> {
+    Ok(parse_input(input)?
+        .all_antinodes(&PART_ONE)
         .len()
         .to_string())
 }
 
 pub fn solve_two(input: &str) -> Result<String> {
-    let Input { antennas, max_i_j } = parse_input(input);
-    Ok(antennas
-        .iter()
-        .flat_map(|(_, antennas)| {
-            (0..antennas.len() - 1)
-                .flat_map(|a| (a + 1..antennas.len()).map(move |b| (a, b)))
-                .flat_map(|(a, b)| {
-                    let mut antinodes = Vec::new();
-                    let mut anti_a = antennas[a];
-                    let mut anti_b = antennas[b];
-                    let diff = (anti_a.0.abs_diff(anti_b.0), anti_a.1.abs_diff(anti_b.1));
-                    let (a_v_dir, b_v_dir) = if anti_a.0 < anti_b.0 {
-                        (VDir::U, VDir::D)
-                    } else {
-                        (VDir::D, VDir::U)
-                    };
-                    let (a_h_dir, b_h_dir) = if anti_a.1 < anti_b.1 {
-                        (HDir::L, HDir::R)
-                    } else {
-                        (HDir::R, HDir::L)
-                    };
-                    antinodes.push(anti_a);
-                    while let Some(next_a) = next_anti(anti_a, diff, (&a_v_dir, &a_h_dir), max_i_j)
-                    {
-                        antinodes.push(next_a);
-                        anti_a = next_a;
-                    }
-                    antinodes.push(anti_b);
-                    while let Some(next_b) = next_anti(anti_b, diff, (&b_v_dir, &b_h_dir), max_i_j)
-                    {
-                        antinodes.push(next_b);
-                        anti_b = next_b;
-                    }
-                    antinodes
-                })
-        })
-        .collect::<HashSet<(usize, usize)>>()
+    Ok(parse_input(input)?
+        .all_antinodes(&PART_TWO)
         .len()
         .to_string())
 }
-
-enum VDir {
-    U,
-    D,
-}
-enum HDir {
-    L,
-    R,
-}
-fn next_anti(
-    cur: (usize, usize),
-    diff: (usize, usize),
-    dir: (&VDir, &HDir),
-    max_i_j: (usize, usize),
-) -> Option<(usize, usize)> {
-    let i = match dir.0 {
-        VDir::U => cur.0.checked_sub(diff.0)?,
-        VDir::D => cur.0.checked_add(diff.0).filter(|&i| i < max_i_j.0)?,
-    };
-    let j = match dir.1 {
-        HDir::L => cur.1.checked_sub(diff.1)?,
-        HDir::R => cur.1.checked_add(diff.1).filter(|&j| j < max_i_j.1)?,
-    };
-    Some((i, j))
-}
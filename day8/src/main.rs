@@ -1,6 +1,47 @@
+use anyhow::Result;
+use utils::clap;
 use utils::derive::aoc;
 
-#[aoc(day8, part1)]
+#[derive(Debug, clap::Args)]
+struct Cli {
+    /// fewest steps beyond an antenna for an antinode, prints the antinode count instead of
+    /// solving when any harmonic option is given
+    #[arg(long, value_name = "K")]
+    min_harmonic: Option<usize>,
+    /// most steps beyond an antenna for an antinode, unlimited if not given
+    #[arg(long, value_name = "K")]
+    max_harmonic: Option<usize>,
+    /// step by the difference between antennas rather than the smallest grid step along it
+    #[arg(long)]
+    raw: bool,
+    /// draw each frequency's antennas and antinodes, with part two harmonics unless others
+    /// are given
+    #[arg(long)]
+    render: bool,
+}
+impl utils::DayArgs for Cli {
+    fn run(&self, input: &str) -> Result<bool> {
+        let custom = self.min_harmonic.is_some() || self.max_harmonic.is_some() || self.raw;
+        if !custom && !self.render {
+            return Ok(false);
+        }
+        let harmonics = day8::Harmonics {
+            min: self.min_harmonic.unwrap_or(0),
+            max: self.max_harmonic,
+            reduce: !self.raw,
+        };
+        let map = day8::parse_input(input)?;
+        if self.render {
+            for frequency in map.antennas.keys() {
+                println!("{frequency}:\n{}\n", map.render(*frequency, &harmonics));
+            }
+        }
+        println!("antinodes:\n{}", map.all_antinodes(&harmonics).len());
+        Ok(true)
+    }
+}
+
+#[aoc(day8, part1, args = Cli)]
 fn solve_one(input: &str) -> anyhow::Result<String> {
     day8::solve_one(input)
 }
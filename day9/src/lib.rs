@@ -1,117 +1,117 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
 use anyhow::{anyhow, Result};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum Block {
-    File(usize),
-    Free,
+/// run of blocks on the disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    start: usize,
+    len: usize,
+}
+impl Span {
+    /// checksum of the span filled with file `id`
+    fn checksum(&self, id: usize) -> u128 {
+        let (start, len) = (self.start as u128, self.len as u128);
+        id as u128 * (start * len + len * len.saturating_sub(1) / 2)
+    }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Block>> {
-    Ok(input
-        .trim()
-        .chars()
-        .collect::<Vec<char>>()
-        .chunks(2)
-        .enumerate()
-        .map(|(id, file_free)| {
-            let mut blocks = vec![];
-
-            let file_len = file_free[0]
-                .to_digit(10)
-                .ok_or_else(|| anyhow!(format!("expecting digit - found {file_free:?}")))?;
-            (0..file_len).for_each(|_| blocks.push(Block::File(id)));
+/// disk map as runs, files are indexed by id
+struct Disk {
+    files: Vec<Span>,
+    free: Vec<Span>,
+}
 
-            let free_len = file_free.get(1).map(|c| {
-                c.to_digit(10)
-                    .ok_or_else(|| anyhow!(format!("expecting digit - found {file_free:?}")))
-            });
-            if let Some(free_len) = free_len {
-                (0..free_len?).for_each(|_| blocks.push(Block::Free));
+fn parse_input(input: &str) -> Result<Disk> {
+    let mut disk = Disk {
+        files: vec![],
+        free: vec![],
+    };
+    let mut start = 0;
+    for (i, c) in input.trim().chars().enumerate() {
+        let len = c
+            .to_digit(10)
+            .ok_or_else(|| anyhow!(format!("expecting digit - found {c:?}")))?
+            as usize;
+        let span = Span { start, len };
+        if i % 2 == 0 {
+            disk.files.push(span);
+        } else if len > 0 {
+            // a zero length file leaves the free runs either side of it as one
+            match disk.free.last_mut() {
+                Some(last) if last.start + last.len == start => last.len += len,
+                _ => disk.free.push(span),
             }
-
-            Ok(blocks)
-        })
-        .collect::<Result<Vec<_>>>()?
-        .into_iter()
-        .flatten()
-        .collect())
+        }
+        start += len;
+    }
+    Ok(disk)
 }
 
 pub fn solve_one(input: &str) -> Result<String> {
-    let mut blocks = parse_input(input)?;
-    let mut start = 0;
-    let mut end = blocks.len() - 1;
-    while start < end {
-        match blocks[start] {
-            Block::File(_) => start += 1,
-            Block::Free => match blocks[end] {
-                Block::Free => end -= 1,
-                Block::File(_) => blocks.swap(start, end),
-            },
+    let Disk { mut files, free } = parse_input(input)?;
+    let mut checksum = 0;
+    // move blocks from the end of the last file into each free span in turn
+    for mut free in free {
+        while free.len > 0 {
+            let Some(id) = files.len().checked_sub(1) else {
+                break;
+            };
+            let file = &mut files[id];
+            if file.start < free.start {
+                break;
+            }
+            let moved = free.len.min(file.len);
+            checksum += Span {
+                start: free.start,
+                len: moved,
+            }
+            .checksum(id);
+            free.start += moved;
+            free.len -= moved;
+            file.len -= moved;
+            if file.len == 0 {
+                files.pop();
+            }
         }
     }
-    Ok(blocks
-        .into_iter()
+    checksum += files
+        .iter()
         .enumerate()
-        .map_while(|(pos, block)| match block {
-            Block::File(id) => Some(pos * id),
-            Block::Free => None,
-        })
-        .sum::<usize>()
-        .to_string())
+        .map(|(id, file)| file.checksum(id))
+        .sum::<u128>();
+    Ok(checksum.to_string())
 }
 
 pub fn solve_two(input: &str) -> Result<String> {
-    let mut blocks = parse_input(input)?;
-    let mut start = 0;
-    let mut end = blocks.len() - 1;
-    while start < end {
-        if matches!(blocks[start], Block::File(_)) {
-            start += 1;
+    let Disk { mut files, free } = parse_input(input)?;
+    let max_len = free.iter().map(|free| free.len).max().unwrap_or(0);
+    // start of every free span, by length
+    let mut free_by_len = vec![BinaryHeap::new(); max_len + 1];
+    for free in free {
+        free_by_len[free.len].push(Reverse(free.start));
+    }
+    for file in files.iter_mut().rev() {
+        // leftmost free span the file fits in
+        let Some((len, start)) = (file.len..=max_len)
+            .filter_map(|len| free_by_len[len].peek().map(|Reverse(start)| (len, *start)))
+            .filter(|(_, start)| *start < file.start)
+            .min_by_key(|(_, start)| *start)
+        else {
             continue;
-        }
-
-        let id = match blocks[end] {
-            Block::Free => {
-                end -= 1;
-                continue;
-            }
-            Block::File(id) => {
-                end -= 1;
-                id
-            }
         };
-        let mut file_len = 1;
-        while blocks[end] == Block::File(id) {
-            end -= 1;
-            file_len += 1;
-        }
-
-        let mut free = (start + 1, 1);
-        while free.1 < file_len && free.0 <= end {
-            match blocks[free.0] {
-                Block::File(_) => free.1 = 0,
-                Block::Free => free.1 += 1,
-            }
-            free.0 += 1;
-        }
-
-        if free.1 == file_len {
-            let mut file = end;
-            (0..file_len).for_each(|_| {
-                free.0 -= 1;
-                file += 1;
-                blocks.swap(free.0, file);
-            })
+        free_by_len[len].pop();
+        file.start = start;
+        if len > file.len {
+            free_by_len[len - file.len].push(Reverse(start + file.len));
         }
+        // the space the file leaves is right of every file still to move, so never used
     }
-    Ok(blocks
-        .into_iter()
+    Ok(files
+        .iter()
         .enumerate()
-        .filter_map(|(pos, block)| match block {
-            Block::File(id) => Some(pos * id),
-            Block::Free => None,
-        })
-        .sum::<usize>()
+        .map(|(id, file)| file.checksum(id))
+        .sum::<u128>()
         .to_string())
 }
@@ -0,0 +1,148 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// hit/miss counts for a [`Memo`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MemoStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub len: usize,
+}
+
+/// memoization cache for recursive solvers
+///
+/// lookups can use a borrowed form of the key (e.g. `&[T]` for `Vec<T>` keys), the key is
+/// only cloned when a new value is stored
+#[derive(Debug)]
+pub struct Memo<K, V> {
+    map: HashMap<K, V>,
+    limit: Option<usize>,
+    hits: u64,
+    misses: u64,
+}
+impl<K, V> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self {
+            map: HashMap::new(),
+            limit: None,
+            hits: 0,
+            misses: 0,
+        }
+    }
+}
+impl<K: Eq + Hash, V: Clone> Memo<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// memo that stops storing new values once it holds `limit` entries
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..Self::default()
+        }
+    }
+
+    /// get the cached value for `key`, or compute it with `f` and cache it
+    ///
+    /// `f` is passed the memo so it can recurse
+    pub fn get_or_compute<Q>(&mut self, key: &Q, f: impl FnOnce(&mut Self) -> V) -> V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        if let Some(v) = self.map.get(key) {
+            self.hits += 1;
+            return v.clone();
+        }
+        self.misses += 1;
+        let v = f(self);
+        if self.limit.is_none_or(|limit| self.map.len() < limit) {
+            self.map.insert(key.to_owned(), v.clone());
+        }
+        v
+    }
+
+    pub fn stats(&self) -> MemoStats {
+        MemoStats {
+            hits: self.hits,
+            misses: self.misses,
+            len: self.map.len(),
+        }
+    }
+
+    /// remove all cached values and reset the stats
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fib(memo: &mut Memo<u64, u64>, n: u64) -> u64 {
+        memo.get_or_compute(&n, |memo| match n {
+            0 | 1 => n,
+            _ => fib(memo, n - 1) + fib(memo, n - 2),
+        })
+    }
+
+    #[test]
+    fn hits_and_misses() {
+        let mut memo = Memo::new();
+        assert_eq!(fib(&mut memo, 10), 55);
+        assert_eq!(
+            memo.stats(),
+            MemoStats {
+                hits: 8,
+                misses: 11,
+                len: 11
+            }
+        );
+        assert_eq!(fib(&mut memo, 10), 55);
+        assert_eq!(memo.stats().hits, 9);
+    }
+
+    #[test]
+    fn borrowed_keys() {
+        let mut memo = Memo::<Vec<u8>, usize>::new();
+        assert_eq!(memo.get_or_compute(&b"abc"[..], |_| 3), 3);
+        assert_eq!(memo.get_or_compute(&b"abc"[..], |_| unreachable!()), 3);
+        assert_eq!(memo.stats().len, 1);
+    }
+
+    #[test]
+    fn limit() {
+        let mut memo = Memo::with_limit(2);
+        for key in [1, 2, 3] {
+            assert_eq!(memo.get_or_compute(&key, |_| key * 10), key * 10);
+        }
+        assert_eq!(memo.stats().len, 2);
+        // stored before the limit was reached
+        assert_eq!(memo.get_or_compute(&1, |_| 0), 10);
+        // computed again each time once full
+        assert_eq!(memo.get_or_compute(&3, |_| 0), 0);
+        assert_eq!(
+            memo.stats(),
+            MemoStats {
+                hits: 1,
+                misses: 4,
+                len: 2
+            }
+        );
+    }
+
+    #[test]
+    fn clear() {
+        let mut memo = Memo::new();
+        fib(&mut memo, 5);
+        memo.clear();
+        assert_eq!(memo.stats(), MemoStats::default());
+        assert_eq!(memo.get_or_compute(&5, |_| 0), 0);
+        assert_eq!(memo.stats().misses, 1);
+    }
+}
@@ -5,7 +5,11 @@ use syn::{parse_macro_input, parse_quote, ItemFn};
 
 #[proc_macro_attribute]
 pub fn aoc(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let (d, p) = parse_attr(attr).unwrap();
+    let (d, p, day_args) = parse_attr(attr).unwrap();
+    let day_args: syn::Path = match day_args {
+        Some(day_args) => syn::parse_str(&day_args).unwrap(),
+        None => parse_quote!(utils::NoArgs),
+    };
     let mut func = parse_macro_input!(item as ItemFn);
     func.vis = parse_quote!(pub);
     let gen = if p == 1 {
@@ -21,10 +25,14 @@ pub fn aoc(attr: TokenStream, item: TokenStream) -> TokenStream {
             }
             fn main() -> anyhow::Result<()> {
                 use utils::Parser;
-                let args = utils::Args::parse();
+                let args = <utils::Args<#day_args>>::parse();
 
                 let input = args.get_input(#d)?;
 
+                if utils::DayArgs::run(&args.day, &input)? {
+                    return Ok(());
+                }
+
                 if args.run_one() {
                     let one = inner_one::solve_one(&input)?;
                     println!("part one:\n{}", one);
@@ -54,7 +62,7 @@ pub fn aoc(attr: TokenStream, item: TokenStream) -> TokenStream {
     gen.into()
 }
 
-fn parse_attr(attr: TokenStream) -> Result<(i32, i32)> {
+fn parse_attr(attr: TokenStream) -> Result<(i32, i32, Option<String>)> {
     let mut i = attr.into_iter().filter_map(|t| {
         if let TokenTree::Ident(_) = t {
             Some(t.to_string())
@@ -99,9 +107,20 @@ fn parse_attr(attr: TokenStream) -> Result<(i32, i32)> {
                     }
                 })
         })?;
+    let day_args = match i.next().as_deref() {
+        None => None,
+        Some("args") => Some(
+            i.next()
+                .ok_or(anyhow!("expecting 'args = <type>' third attribute"))?,
+        ),
+        Some(n) => return Err(anyhow!("unexpected attr - {}", n)),
+    };
+    if day_args.is_some() && p != 1 {
+        return Err(anyhow!("'args = <type>' only expected on part1"));
+    }
     if let Some(n) = i.next() {
         Err(anyhow!("unexpected attr - {}", n))
     } else {
-        Ok((d, p))
+        Ok((d, p, day_args))
     }
 }
@@ -9,15 +9,34 @@ use std::collections::HashMap;
 use std::env;
 use std::fs;
 
+pub mod ppm;
+
 pub use crate::UtilArgs as Args;
+pub use clap;
 pub use clap::Parser;
+pub use memo;
 pub extern crate utils_derive as derive;
 
 const YEAR: i32 = 2024;
 
+/// extra day specific arguments, flattened into [`UtilArgs`]
+pub trait DayArgs: clap::Args {
+    /// run any day specific mode on the input, returns true if the parts should not be run
+    fn run(&self, input: &str) -> Result<bool>;
+}
+
+/// no day specific arguments
+#[derive(Debug, clap::Args)]
+pub struct NoArgs {}
+impl DayArgs for NoArgs {
+    fn run(&self, _input: &str) -> Result<bool> {
+        Ok(false)
+    }
+}
+
 #[derive(Debug, Parser)]
 #[command(long_about = None)]
-pub struct UtilArgs {
+pub struct UtilArgs<D: DayArgs = NoArgs> {
     /// input file, AOC_SESSION env must be set if not specified
     #[arg(short, long)]
     input: Option<String>,
@@ -30,8 +49,10 @@ pub struct UtilArgs {
     /// submit the answers to AOC
     #[arg(short, long)]
     submit: bool,
+    #[command(flatten)]
+    pub day: D,
 }
-impl UtilArgs {
+impl<D: DayArgs> UtilArgs<D> {
     pub fn get_input(&self, day: i32) -> Result<String> {
         if let Some(file) = &self.input {
             Ok(fs::read_to_string(file)?)
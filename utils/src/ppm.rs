@@ -0,0 +1,16 @@
+/// binary ppm image of a grid with each cell as a `cell` pixel square coloured by `colour`
+pub fn from_grid<T>(grid: &[Vec<T>], cell: usize, colour: impl Fn(&T) -> [u8; 3]) -> Vec<u8> {
+    let height = grid.len() * cell;
+    let width = grid.first().map_or(0, |l| l.len()) * cell;
+    let mut ppm = format!("P6\n{width} {height}\n255\n").into_bytes();
+    for l in grid {
+        let row = l
+            .iter()
+            .flat_map(|c| colour(c).repeat(cell))
+            .collect::<Vec<_>>();
+        for _ in 0..cell {
+            ppm.extend(&row);
+        }
+    }
+    ppm
+}